@@ -0,0 +1,160 @@
+//!Times `Tree` against `BTreeMap`, and against cloning a `BTreeMap` for every version.
+//!Run with `cargo bench`, optionally passing the number of keys: `cargo bench -- 200000`.
+
+extern crate persistent_rope;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::env;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use persistent_rope::Tree;
+
+///Passes everything on to the system allocator, counting allocations on the way
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		BYTES.fetch_add(new_size, Ordering::Relaxed);
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+///Time taken and allocations made by one run
+struct Measurement {
+	time: Duration,
+	allocations: usize,
+	bytes: usize,
+}
+
+///Runs the closure once, dropping its result outside the measurement
+fn measure<F,R>(f: F) -> (Measurement, R) where F: FnOnce() -> R {
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+	let bytes = BYTES.load(Ordering::Relaxed);
+	let start = Instant::now();
+	let result = black_box(f());
+	let time = start.elapsed();
+	let measurement = Measurement {
+		time,
+		allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+		bytes: BYTES.load(Ordering::Relaxed) - bytes,
+	};
+	(measurement, result)
+}
+
+fn report(workload: &str, structure: &str, measurement: &Measurement) {
+	println!("{:<24} {:<20} {:>10.2} ms {:>12} allocs {:>14} bytes", workload, structure, measurement.time.as_secs_f64() * 1000.0, measurement.allocations, measurement.bytes);
+}
+
+///xorshift64*, enough to shuffle keys the same way on every run
+struct Rng(u64);
+
+impl Rng {
+	fn below(&mut self, bound: usize) -> usize {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		(self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % bound
+	}
+}
+
+fn shuffled(n: usize, rng: &mut Rng) -> Vec<u64> {
+	let mut keys: Vec<u64> = (0..n as u64).collect();
+	for i in (1..keys.len()).rev() {
+		keys.swap(i, rng.below(i + 1));
+	}
+	keys
+}
+
+fn tree_inserts(keys: &[u64]) -> Tree<u64,u64> {
+	keys.iter().fold(Tree::new(), |tree, &key| tree.insert(key, key).unwrap())
+}
+
+fn map_inserts(keys: &[u64]) -> BTreeMap<u64,u64> {
+	let mut map = BTreeMap::new();
+	for &key in keys {
+		map.insert(key, key);
+	}
+	map
+}
+
+fn inserts(workload: &str, keys: &[u64]) {
+	let (measurement, _) = measure(|| tree_inserts(keys));
+	report(workload, "Tree", &measurement);
+	let (measurement, _) = measure(|| map_inserts(keys));
+	report(workload, "BTreeMap", &measurement);
+}
+
+fn lookups(keys: &[u64], order: &[u64]) {
+	let tree = tree_inserts(keys);
+	let map = map_inserts(keys);
+
+	let (measurement, _) = measure(|| order.iter().filter(|key| tree.find(*key).is_some()).count());
+	report("find", "Tree", &measurement);
+	let (measurement, _) = measure(|| order.iter().filter(|key| map.contains_key(*key)).count());
+	report("find", "BTreeMap", &measurement);
+}
+
+///Every intermediate version stays alive until the end
+fn all_versions(keys: &[u64]) {
+	let (measurement, versions) = measure(|| {
+		let mut versions = vec![Tree::new()];
+		for &key in keys {
+			let next = versions.last().unwrap().insert(key, key).unwrap();
+			versions.push(next);
+		}
+		versions
+	});
+	report("keep every version", "Tree", &measurement);
+	drop(versions);
+
+	let (measurement, versions) = measure(|| {
+		let mut versions = vec![BTreeMap::new()];
+		for &key in keys {
+			let mut next = versions.last().unwrap().clone();
+			next.insert(key, key);
+			versions.push(next);
+		}
+		versions
+	});
+	report("keep every version", "BTreeMap clones", &measurement);
+	drop(versions);
+}
+
+fn main() {
+	//cargo passes --bench along, so take the first argument that parses
+	let n = env::args().skip(1).find_map(|arg| arg.parse().ok()).unwrap_or(100_000);
+	//cloning per version is quadratic, so that workload gets fewer keys
+	let versions = (n / 20).clamp(1, 5_000);
+	let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+	let random = shuffled(n, &mut rng);
+	let sequential: Vec<u64> = (0..n as u64).collect();
+	let reverse: Vec<u64> = (0..n as u64).rev().collect();
+
+	println!("{} keys, {} for version workloads", n, versions);
+	inserts("insert random", &random);
+	inserts("insert sequential", &sequential);
+	inserts("insert reverse", &reverse);
+	lookups(&random, &shuffled(n, &mut rng));
+	all_versions(&random[..versions]);
+}
@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+use super::{Comparator, Ptr, Summary, Tree, TreeNode};
+
+///Returned by `Tree::from_sorted_iter` when the input is not strictly increasing
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnsortedInputError {
+	///Position of the first key that is not above its predecessor
+	pub index: usize,
+}
+
+impl fmt::Display for UnsortedInputError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "key at index {} is not above the previous key", self.index)
+	}
+}
+
+impl Error for UnsortedInputError {}
+
+///Builds a balanced subtree from the next entries in order, only nodes on the deepest level are red
+fn build_balanced<K,V,S,I>(entries: &mut I, size: usize, depth: usize, red_depth: usize) -> Ptr<Option<TreeNode<K,V,S>>> where I: Iterator<Item=(K,V)>, S: Summary<K,V> {
+	if size == 0 {
+		return Ptr::new(None);
+	}
+
+	let left_size = size / 2;
+	let left = build_balanced(entries, left_size, depth + 1, red_depth);
+	let entry = Ptr::new(entries.next().unwrap());
+	let right = build_balanced(entries, size - left_size - 1, depth + 1, red_depth);
+	TreeNode::new(depth == red_depth, entry, left, right)
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Builds a tree in linear time from entries in strictly increasing key order, by the default comparator
+	pub fn from_sorted_iter<I>(iter: I) -> Result<Tree<K,V,S,C>, UnsortedInputError> where I: IntoIterator<Item=(K,V)>, C: Default {
+		Self::from_sorted_iter_by(iter, C::default())
+	}
+
+	///Builds a tree in linear time from entries in strictly increasing order by the given comparator
+	pub fn from_sorted_iter_by<I>(iter: I, cmp: C) -> Result<Tree<K,V,S,C>, UnsortedInputError> where I: IntoIterator<Item=(K,V)> {
+		let mut entries: Vec<(K,V)> = Vec::new();
+
+		for (index, (key, val)) in iter.into_iter().enumerate() {
+			if let Some(last) = entries.last() {
+				if cmp.compare(&last.0, &key) != Ordering::Less {
+					return Err(UnsortedInputError {index});
+				}
+			}
+			entries.push((key, val));
+		}
+
+		//deepest level gets coloured red, unless the root is all there is
+		let mut red_depth = 0;
+		while (2 << red_depth) <= entries.len() {
+			red_depth += 1;
+		}
+		if red_depth == 0 {
+			red_depth = usize::MAX;
+		}
+
+		let size = entries.len();
+		Ok(Tree {root: build_balanced(&mut entries.into_iter(), size, 0, red_depth), cmp})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::UnsortedInputError;
+	use super::super::Tree;
+	use super::super::tests::check_tree;
+
+	#[test]
+	fn test_from_sorted_sizes() {
+		for len in 0..200 {
+			let tree = Tree::from_sorted_iter((0..len).map(|key| (key, key * 10))).unwrap();
+			check_tree(&tree);
+			assert_eq!(tree.len(), len as usize);
+			assert!(tree.iter().map(|(key, val)| (*key, *val)).eq((0..len).map(|key| (key, key * 10))));
+		}
+	}
+
+	#[test]
+	fn test_from_sorted_then_modify() {
+		let tree = Tree::from_sorted_iter((0..100).map(|key| (key * 2, key))).unwrap();
+		let tree = tree.insert(51, 0).unwrap();
+		check_tree(&tree);
+		let (tree, _) = tree.remove(&50).unwrap();
+		check_tree(&tree);
+		assert_eq!(tree.len(), 100);
+	}
+
+	#[test]
+	fn test_from_sorted_rejects_unsorted() {
+		let err = Tree::<i32,()>::from_sorted_iter(vec![(1, ()), (3, ()), (2, ())]).err().unwrap();
+		assert_eq!(err, UnsortedInputError {index: 2});
+
+		let err = Tree::<i32,()>::from_sorted_iter(vec![(1, ()), (1, ())]).err().unwrap();
+		assert_eq!(err.index, 1);
+		assert_eq!(err.to_string(), "key at index 1 is not above the previous key");
+	}
+}
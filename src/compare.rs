@@ -0,0 +1,123 @@
+use std::cmp::Ordering;
+
+use super::Ptr;
+
+///Ordering of keys in a tree, used in place of their `Ord` impl
+pub trait Comparator<T: ?Sized> {
+	fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+///Orders keys by their `Ord` impl, the default for every tree
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Natural;
+
+impl<T> Comparator<T> for Natural where T: Ord + ?Sized {
+	fn compare(&self, a: &T, b: &T) -> Ordering {
+		a.cmp(b)
+	}
+}
+
+///Reverses the ordering of another comparator
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reverse<C>(pub C);
+
+impl<T,C> Comparator<T> for Reverse<C> where T: ?Sized, C: Comparator<T> {
+	fn compare(&self, a: &T, b: &T) -> Ordering {
+		self.0.compare(b, a)
+	}
+}
+
+///Shared comparators, which covers trait objects as `Ptr<dyn Comparator<K>>`
+impl<T,C> Comparator<T> for Ptr<C> where T: ?Sized, C: Comparator<T> + ?Sized {
+	fn compare(&self, a: &T, b: &T) -> Ordering {
+		(**self).compare(a, b)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cmp::Ordering;
+	use std::ops::Bound;
+
+	use super::{Comparator, Natural, Reverse};
+	use super::super::{Ptr, Tree, TreeTransient};
+
+	#[derive(Clone, Copy, Debug, Default)]
+	struct CaseInsensitive;
+
+	impl Comparator<str> for CaseInsensitive {
+		fn compare(&self, a: &str, b: &str) -> Ordering {
+			a.bytes().map(|byte| byte.to_ascii_lowercase()).cmp(b.bytes().map(|byte| byte.to_ascii_lowercase()))
+		}
+	}
+
+	impl Comparator<String> for CaseInsensitive {
+		fn compare(&self, a: &String, b: &String) -> Ordering {
+			Comparator::<str>::compare(self, a, b)
+		}
+	}
+
+	#[test]
+	fn test_case_insensitive() {
+		let tree = Tree::with_comparator(CaseInsensitive)
+			.insert("banana".to_owned(), 2).unwrap()
+			.insert("Apple".to_owned(), 1).unwrap()
+			.insert("cherry".to_owned(), 3).unwrap();
+
+		assert!(tree.insert("APPLE".to_owned(), 4).is_none());
+		assert_eq!(tree.find("BANANA"), Some(&2));
+		assert_eq!(tree.keys().map(|key| key.as_str()).collect::<Vec<_>>(), vec!["Apple", "banana", "cherry"]);
+
+		let (tree, val) = tree.remove("CHERRY").unwrap();
+		assert_eq!(val, 3);
+		assert_eq!(tree.len(), 2);
+		assert_eq!(tree.check_invariants(), vec![]);
+	}
+
+	#[test]
+	fn test_reverse() {
+		let tree = (0..100).fold(Tree::with_comparator(Reverse(Natural)), |tree, key| tree.insert(key, key * 10).unwrap());
+		assert_eq!(tree.check_invariants(), vec![]);
+		assert!(tree.keys().cloned().eq((0..100).rev()));
+		assert_eq!(tree.first(), Some((&99, &990)));
+		assert_eq!(tree.floor(&50), Some((&50, &500)));
+		assert_eq!(tree.successor(&50), Some((&49, &490)));
+		assert!(tree.range((Bound::Included(&60), Bound::Included(&40))).map(|(key, _)| *key).eq((40..61).rev()));
+
+		let (left, found, right) = tree.split(&30);
+		assert_eq!(found, Some(300));
+		assert!(left.keys().all(|&key| key > 30));
+		assert!(right.keys().all(|&key| key < 30));
+
+		let odds = (0..50).fold(Tree::with_comparator(Reverse(Natural)), |tree, key| tree.insert(key * 2 + 1, 0).unwrap());
+		assert!(tree.difference(&odds).keys().all(|&key| key % 2 == 0));
+	}
+
+	#[test]
+	fn test_reverse_constructors() {
+		let tree: Tree<i32,i32,(),_> = Tree::from_sorted_iter_by((0..100).rev().map(|key| (key, key * 10)), Reverse(Natural)).unwrap();
+		assert_eq!(tree.check_invariants(), vec![]);
+		assert!(tree.keys().cloned().eq((0..100).rev()));
+		assert_eq!(Tree::<i32,i32,(),_>::from_sorted_iter_by(vec![(1, 0), (2, 0)], Reverse(Natural)).err().unwrap().index, 1);
+
+		let loaded: Vec<Tree<i32,i32,(),_>> = Tree::read_snapshot_with(&Tree::write_snapshot(vec![&tree]), Reverse(Natural)).unwrap();
+		assert!(loaded[0].keys().cloned().eq((0..100).rev()));
+		assert_eq!(loaded[0].insert(100, 0).unwrap().first(), Some((&100, &0)));
+
+		let mut transient = TreeTransient::with_comparator(Reverse(Natural));
+		for key in 0..100 {
+			transient.insert(key, key * 10);
+		}
+		let frozen = transient.freeze();
+		assert!(frozen.iter().eq(tree.iter()));
+	}
+
+	#[test]
+	fn test_trait_object() {
+		let cmp: Ptr<dyn Comparator<i32>> = Ptr::new(Reverse(Natural));
+		let tree = (0..20).fold(Tree::with_comparator(cmp), |tree, key| tree.insert(key, ()).unwrap());
+
+		assert!(tree.keys().cloned().eq((0..20).rev()));
+		assert_eq!(tree.check_invariants(), vec![]);
+	}
+}
@@ -0,0 +1,194 @@
+use std::cmp::Ordering;
+
+use super::{Comparator, HasSize, Natural, Ptr, Summary, Tree, TreeNode};
+
+///Difference between two versions at a single key
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change<'a,K,V> {
+	///Key only present in the new version
+	Added(&'a K, &'a V),
+	///Key only present in the old version
+	Removed(&'a K, &'a V),
+	///Key present in both with different values, old value first
+	Changed(&'a K, &'a V, &'a V),
+}
+
+///Work left on one side of a diff, next in order on top of the stack
+enum Pending<'a,K,V,S> {
+	///Whole subtree not looked into yet
+	Subtree(&'a Ptr<Option<TreeNode<K,V,S>>>),
+	///Single entry whose subtrees have already been pushed
+	Entry(&'a TreeNode<K,V,S>),
+}
+
+///In-order iterator over the changes from one version to another
+pub struct Diff<'a,K,V,S=(),C=Natural> {
+	old: Vec<Pending<'a,K,V,S>>,
+	new: Vec<Pending<'a,K,V,S>>,
+	cmp: &'a C,
+}
+
+///Replaces the subtree on top of the stack by its left subtree, entry and right subtree
+fn expand<'a,K,V,S>(stack: &mut Vec<Pending<'a,K,V,S>>) {
+	if let Some(Pending::Subtree(current)) = stack.pop() {
+		if let Some(ref node) = **current {
+			stack.push(Pending::Subtree(&node.right));
+			stack.push(Pending::Entry(node));
+			stack.push(Pending::Subtree(&node.left));
+		}
+	}
+}
+
+impl<'a,K,V,S,C> Iterator for Diff<'a,K,V,S,C> where C: Comparator<K>, V: PartialEq {
+	type Item = Change<'a,K,V>;
+
+	fn next(&mut self) -> Option<Change<'a,K,V>> {
+		loop {
+			let (old_node, new_node) = match (self.old.last(), self.new.last()) {
+				(None, None) => return None,
+				(Some(&Pending::Subtree(old)), Some(&Pending::Subtree(new))) => {
+					if Ptr::ptr_eq(old, new) {
+						//same allocation on both sides, nothing below can differ
+						self.old.pop();
+						self.new.pop();
+					} else if old.size() >= new.size() {
+						//open up the larger side first, so shared subtrees come to the top whole
+						expand(&mut self.old);
+					} else {
+						expand(&mut self.new);
+					}
+					continue;
+				},
+				(Some(&Pending::Subtree(_)), _) => {
+					expand(&mut self.old);
+					continue;
+				},
+				(_, Some(&Pending::Subtree(_))) => {
+					expand(&mut self.new);
+					continue;
+				},
+				(Some(&Pending::Entry(old)), Some(&Pending::Entry(new))) => (old, new),
+				(Some(&Pending::Entry(old)), None) => {
+					self.old.pop();
+					return Some(Change::Removed(old.key(), old.val()));
+				},
+				(None, Some(&Pending::Entry(new))) => {
+					self.new.pop();
+					return Some(Change::Added(new.key(), new.val()));
+				}
+			};
+
+			match self.cmp.compare(old_node.key(), new_node.key()) {
+				Ordering::Less => {
+					self.old.pop();
+					return Some(Change::Removed(old_node.key(), old_node.val()));
+				},
+				Ordering::Greater => {
+					self.new.pop();
+					return Some(Change::Added(new_node.key(), new_node.val()));
+				},
+				Ordering::Equal => {
+					self.old.pop();
+					self.new.pop();
+					if !Ptr::ptr_eq(&old_node.entry, &new_node.entry) && old_node.val() != new_node.val() {
+						return Some(Change::Changed(old_node.key(), old_node.val(), new_node.val()));
+					}
+				}
+			}
+		}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Changes from the old version to the new one in key order, by the old version's comparator.
+	///Subtrees the two versions share are skipped, so the cost follows the size of the change.
+	pub fn diff<'a>(old: &'a Self, new: &'a Self) -> Diff<'a,K,V,S,C> where V: PartialEq {
+		Diff {old: vec![Pending::Subtree(&old.root)], new: vec![Pending::Subtree(&new.root)], cmp: &old.cmp}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+	use std::cmp::Ordering;
+
+	use super::Change;
+	use super::super::Tree;
+	use super::super::tests::build;
+
+	#[test]
+	fn test_diff() {
+		let old = build(0..20);
+		let (new, _) = old.remove(&5).unwrap();
+		let new = new.insert(25, 250).unwrap();
+		let (new, _) = new.insert_or_replace(12, -1);
+		let (new, _) = new.insert_or_replace(13, 130);
+
+		let changes: Vec<_> = Tree::diff(&old, &new).collect();
+		assert_eq!(changes, vec![
+			Change::Removed(&5, &50),
+			Change::Changed(&12, &120, &-1),
+			Change::Added(&25, &250)
+		]);
+
+		assert_eq!(Tree::diff(&old, &old).count(), 0);
+		assert_eq!(Tree::diff(&Tree::new(), &old).count(), 20);
+		assert!(Tree::diff(&old, &Tree::new()).all(|change| matches!(change, Change::Removed(..))));
+	}
+
+	#[test]
+	fn test_diff_unrelated() {
+		//built separately, so nothing is shared and every entry gets compared
+		let old = build((0..200).map(|key| key * 2));
+		let new = build((0..200).map(|key| key * 3));
+
+		let mut expected = Vec::new();
+		for key in 0..600 {
+			match (old.find(&key), new.find(&key)) {
+				(Some(a), Some(b)) if a == b => {},
+				(a, b) if a.is_some() || b.is_some() => expected.push((key, a.cloned(), b.cloned())),
+				_ => {}
+			}
+		}
+
+		let changes: Vec<_> = Tree::diff(&old, &new).map(|change| match change {
+			Change::Added(key, val) => (*key, None, Some(*val)),
+			Change::Removed(key, val) => (*key, Some(*val), None),
+			Change::Changed(key, a, b) => (*key, Some(*a), Some(*b))
+		}).collect();
+		assert_eq!(changes, expected);
+	}
+
+	thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+
+	///Key that counts how often it gets compared
+	#[derive(PartialEq, Eq, Debug)]
+	struct Counted(i32);
+
+	impl PartialOrd for Counted {
+		fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+			Some(self.cmp(other))
+		}
+	}
+
+	impl Ord for Counted {
+		fn cmp(&self, other: &Counted) -> Ordering {
+			COMPARISONS.with(|count| count.set(count.get() + 1));
+			self.0.cmp(&other.0)
+		}
+	}
+
+	#[test]
+	fn test_diff_prunes_shared() {
+		let old = (0..10000).fold(Tree::new(), |tree, key| tree.insert(Counted(key), key).unwrap());
+		let (new, _) = old.insert_or_replace(Counted(5000), -1);
+		let new = new.insert(Counted(20000), 0).unwrap();
+
+		COMPARISONS.with(|count| count.set(0));
+		let changes: Vec<_> = Tree::diff(&old, &new).collect();
+		let comparisons = COMPARISONS.with(|count| count.get());
+
+		assert_eq!(changes, vec![Change::Changed(&Counted(5000), &5000, &-1), Change::Added(&Counted(20000), &0)]);
+		assert!(comparisons < 200, "{} comparisons for a two entry change", comparisons);
+	}
+}
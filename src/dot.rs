@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Write};
+
+use super::{Comparator, Ptr, Summary, Tree, TreeNode};
+
+///Label text with the characters DOT treats specially inside quotes escaped
+fn escape(label: &str) -> String {
+	label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+///Assigns each distinct node an id and writes it the first time it is reached
+struct DotWriter<K,V,S> {
+	ids: HashMap<*const Option<TreeNode<K,V,S>>, usize>,
+	out: String,
+}
+
+impl<K,V,S> DotWriter<K,V,S> where K: Debug {
+	///Writes the subtree unless already drawn, returns the id of its root
+	fn visit(&mut self, current: &Ptr<Option<TreeNode<K,V,S>>>) -> Option<usize> {
+		let node = match **current {
+			None => return None,
+			Some(ref node) => node
+		};
+		if let Some(&id) = self.ids.get(&Ptr::as_ptr(current)) {
+			return Some(id);
+		}
+
+		let id = self.ids.len();
+		self.ids.insert(Ptr::as_ptr(current), id);
+		let colour = if node.is_red { "red" } else { "black" };
+		writeln!(self.out, "\tn{} [label=\"{}\", fillcolor={}];", id, escape(&format!("{:?}", node.key())), colour).unwrap();
+
+		let left = self.visit(&node.left);
+		let right = self.visit(&node.right);
+		//an invisible stand-in keeps a lone child on its own side
+		match (left, right) {
+			(Some(left), Some(right)) => writeln!(self.out, "\tn{} -> n{};\n\tn{} -> n{};", id, left, id, right),
+			(Some(left), None) => writeln!(self.out, "\tn{} -> n{};\n\tnil{} [style=invis];\n\tn{} -> nil{} [style=invis];", id, left, id, id, id),
+			(None, Some(right)) => writeln!(self.out, "\tnil{} [style=invis];\n\tn{} -> nil{} [style=invis];\n\tn{} -> n{};", id, id, id, id, right),
+			(None, None) => Ok(())
+		}.unwrap();
+
+		Some(id)
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Graphviz DOT drawing of this version, with nodes in their red-black colours
+	pub fn to_dot(&self) -> String where K: Debug {
+		Self::versions_to_dot(vec![self])
+	}
+
+	///Graphviz DOT drawing of several versions, each node drawn once however many versions share it.
+	///Every version gets a box labelled with its position pointing at its root, so path copies stand out.
+	pub fn versions_to_dot<'a,I>(versions: I) -> String where I: IntoIterator<Item=&'a Tree<K,V,S,C>>, K: Debug + 'a, V: 'a, S: 'a, C: 'a {
+		let mut writer = DotWriter {
+			ids: HashMap::new(),
+			out: String::from("digraph tree {\n\tordering=out;\n\tnode [shape=circle, style=filled, fontcolor=white];\n"),
+		};
+
+		for (version, tree) in versions.into_iter().enumerate() {
+			writeln!(writer.out, "\tv{} [label=\"v{}\", shape=box, style=solid, fontcolor=black];", version, version).unwrap();
+			if let Some(root) = writer.visit(&tree.root) {
+				writeln!(writer.out, "\tv{} -> n{} [style=dashed];", version, root).unwrap();
+			}
+		}
+
+		writer.out.push_str("}\n");
+		writer.out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::Tree;
+	use super::super::tests::build;
+
+	fn count(dot: &str, pattern: &str) -> usize {
+		dot.matches(pattern).count()
+	}
+
+	#[test]
+	fn test_single_version() {
+		let tree = build([13, 8, 17, 1]);
+		let dot = tree.to_dot();
+
+		assert!(dot.starts_with("digraph tree {\n"));
+		assert!(dot.ends_with("}\n"));
+		assert_eq!(count(&dot, "fillcolor="), 4);
+		assert_eq!(count(&dot, "fillcolor=red"), 1);
+		assert!(dot.contains("label=\"1\", fillcolor=red"));
+		assert!(dot.contains("v0 -> n0 [style=dashed];"));
+		//8 has only a left child, which keeps its side through the hidden stand-in
+		assert!(dot.contains("n1 -> n2;\n\tnil1 [style=invis];"));
+
+		assert_eq!(Tree::<i32,i32>::new().to_dot().matches("->").count(), 0);
+	}
+
+	#[test]
+	fn test_shared_nodes_drawn_once() {
+		let base = build(0..100);
+		let inserted = base.insert(1000, 0).unwrap();
+		let (removed, _) = base.remove(&40).unwrap();
+		let versions = vec![&base, &inserted, &removed];
+
+		let dot = Tree::versions_to_dot(versions.clone());
+		let stats = Tree::memory_stats(versions);
+		assert_eq!(count(&dot, "fillcolor="), stats.nodes);
+		assert_eq!(count(&dot, "[style=dashed]"), 3);
+		assert!(stats.nodes < 3 * 100);
+	}
+
+	#[test]
+	fn test_labels_escaped() {
+		let tree = Tree::new().insert(String::from("say \"hi\""), ()).unwrap();
+		assert!(tree.to_dot().contains(r#"label="\"say \\\"hi\\\"\"""#));
+	}
+}
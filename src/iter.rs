@@ -0,0 +1,202 @@
+use super::{Comparator, HasSize, Ptr, Summary, Tree, TreeNode};
+
+///In-order iterator over the entries of a tree
+pub struct Iter<'a,K,V,S=()> {
+	///Nodes still to be yielded from the front, first remaining one on top
+	front: Vec<&'a TreeNode<K,V,S>>,
+	///Nodes still to be yielded from the back, last remaining one on top
+	back: Vec<&'a TreeNode<K,V,S>>,
+	///Entries left between the two ends
+	remaining: usize,
+}
+
+///In-order iterator over the keys of a tree
+pub struct Keys<'a,K,V,S=()> {
+	inner: Iter<'a,K,V,S>
+}
+
+///In-order iterator over the values of a tree
+pub struct Values<'a,K,V,S=()> {
+	inner: Iter<'a,K,V,S>
+}
+
+fn push_left<'a,K,V,S>(stack: &mut Vec<&'a TreeNode<K,V,S>>, mut current: &'a Ptr<Option<TreeNode<K,V,S>>>) {
+	while let Some(ref node) = **current {
+		stack.push(node);
+		current = &node.left;
+	}
+}
+
+fn push_right<'a,K,V,S>(stack: &mut Vec<&'a TreeNode<K,V,S>>, mut current: &'a Ptr<Option<TreeNode<K,V,S>>>) {
+	while let Some(ref node) = **current {
+		stack.push(node);
+		current = &node.right;
+	}
+}
+
+impl<'a,K,V,S> Iter<'a,K,V,S> {
+	pub(crate) fn new(root: &'a Ptr<Option<TreeNode<K,V,S>>>) -> Iter<'a,K,V,S> {
+		let mut front = Vec::new();
+		let mut back = Vec::new();
+		push_left(&mut front, root);
+		push_right(&mut back, root);
+
+		Iter {front, back, remaining: root.size()}
+	}
+
+	///Stacks must hold the paths to the first and last entries to be yielded
+	pub(crate) fn from_stacks(front: Vec<&'a TreeNode<K,V,S>>, back: Vec<&'a TreeNode<K,V,S>>, remaining: usize) -> Iter<'a,K,V,S> {
+		Iter {front, back, remaining}
+	}
+}
+
+impl<'a,K,V,S> Iterator for Iter<'a,K,V,S> {
+	type Item = (&'a K, &'a V);
+
+	fn next(&mut self) -> Option<(&'a K, &'a V)> {
+		if self.remaining == 0 {
+			//met the back half
+			return None;
+		}
+
+		let node = self.front.pop()?;
+		push_left(&mut self.front, &node.right);
+		self.remaining -= 1;
+		Some((node.key(), node.val()))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(self.remaining, Some(self.remaining))
+	}
+}
+
+impl<'a,K,V,S> DoubleEndedIterator for Iter<'a,K,V,S> {
+	fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+		if self.remaining == 0 {
+			//met the front half
+			return None;
+		}
+
+		let node = self.back.pop()?;
+		push_right(&mut self.back, &node.left);
+		self.remaining -= 1;
+		Some((node.key(), node.val()))
+	}
+}
+
+impl<'a,K,V,S> ExactSizeIterator for Iter<'a,K,V,S> {}
+
+impl<'a,K,V,S> Iterator for Keys<'a,K,V,S> {
+	type Item = &'a K;
+
+	fn next(&mut self) -> Option<&'a K> {
+		self.inner.next().map(|(key, _)| key)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl<'a,K,V,S> DoubleEndedIterator for Keys<'a,K,V,S> {
+	fn next_back(&mut self) -> Option<&'a K> {
+		self.inner.next_back().map(|(key, _)| key)
+	}
+}
+
+impl<'a,K,V,S> ExactSizeIterator for Keys<'a,K,V,S> {}
+
+impl<'a,K,V,S> Iterator for Values<'a,K,V,S> {
+	type Item = &'a V;
+
+	fn next(&mut self) -> Option<&'a V> {
+		self.inner.next().map(|(_, val)| val)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl<'a,K,V,S> DoubleEndedIterator for Values<'a,K,V,S> {
+	fn next_back(&mut self) -> Option<&'a V> {
+		self.inner.next_back().map(|(_, val)| val)
+	}
+}
+
+impl<'a,K,V,S> ExactSizeIterator for Values<'a,K,V,S> {}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Iterates over all entries in key order
+	pub fn iter(&self) -> Iter<'_,K,V,S> {
+		Iter::new(&self.root)
+	}
+
+	///Iterates over all keys in order
+	pub fn keys(&self) -> Keys<'_,K,V,S> {
+		Keys {inner: self.iter()}
+	}
+
+	///Iterates over all values in key order
+	pub fn values(&self) -> Values<'_,K,V,S> {
+		Values {inner: self.iter()}
+	}
+}
+
+impl<'a,K,V,S,C> IntoIterator for &'a Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	type Item = (&'a K, &'a V);
+	type IntoIter = Iter<'a,K,V,S>;
+
+	fn into_iter(self) -> Iter<'a,K,V,S> {
+		self.iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::Tree;
+	use super::super::tests::build;
+
+	#[test]
+	fn test_iter_empty() {
+		let tree: Tree<i32,i32> = Tree::new();
+		assert_eq!(tree.iter().next(), None);
+		assert_eq!(tree.iter().next_back(), None);
+	}
+
+	#[test]
+	fn test_iter_forward_backward() {
+		let tree = build([13, 8, 17, 1, 11, 15, 25, 6, 22, 27]);
+
+		let forward: Vec<_> = tree.keys().cloned().collect();
+		assert_eq!(forward, vec![1, 6, 8, 11, 13, 15, 17, 22, 25, 27]);
+
+		let backward: Vec<_> = tree.values().rev().cloned().collect();
+		assert_eq!(backward, vec![270, 250, 220, 170, 150, 130, 110, 80, 60, 10]);
+
+		let mut count = 0;
+		for (key, val) in &tree {
+			assert_eq!(*val, key * 10);
+			count += 1;
+		}
+		assert_eq!(count, 10);
+	}
+
+	#[test]
+	fn test_iter_meet_in_middle() {
+		let keys: Vec<i32> = (0..50).collect();
+		let tree = build(keys.clone());
+
+		for split in 0..51 {
+			let mut iter = tree.keys();
+			let front: Vec<_> = iter.by_ref().take(split).cloned().collect();
+			let back: Vec<_> = iter.by_ref().rev().cloned().collect();
+
+			assert_eq!(front.len() + back.len(), 50);
+			assert!(front.iter().enumerate().all(|(i, &key)| key == i as i32));
+			assert!(back.iter().rev().enumerate().all(|(i, &key)| key == (split + i) as i32));
+			assert_eq!(iter.next(), None);
+			assert_eq!(iter.next_back(), None);
+		}
+	}
+}
@@ -0,0 +1,1491 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+#[allow(clippy::redundant_field_names)]
+pub mod rcstr;
+#[cfg(feature = "model-check")]
+pub mod model;
+mod bulk;
+mod compare;
+mod diff;
+mod dot;
+mod iter;
+mod order;
+mod range;
+mod render;
+mod set;
+mod set_ops;
+mod snapshot;
+mod split;
+mod stats;
+mod summary;
+mod transient;
+mod validate;
+
+pub use bulk::UnsortedInputError;
+pub use compare::{Comparator, Natural, Reverse};
+pub use diff::{Change, Diff};
+pub use iter::{Iter, Keys, Values};
+pub use range::Range;
+pub use render::Render;
+pub use set::{SetIter, TreeSet};
+pub use snapshot::{Codec, SnapshotError};
+pub use stats::MemoryStats;
+pub use summary::Summary;
+pub use transient::TreeTransient;
+pub use validate::Violation;
+
+use summary::summarise;
+
+//fn ptr_eq<T>(a: *const T, b: *const T) -> bool { a == b }
+
+///Reference-counted pointer shared between tree versions, `Arc` with the `sync` feature
+#[cfg(not(feature = "sync"))]
+pub type Ptr<T> = std::rc::Rc<T>;
+///Reference-counted pointer shared between tree versions, `Arc` with the `sync` feature
+#[cfg(feature = "sync")]
+pub type Ptr<T> = std::sync::Arc<T>;
+
+//Red-Black Tree
+
+pub struct Tree<K,V,S=(),C=Natural> {
+	root: Ptr<Option<TreeNode<K,V,S>>>,
+	///Orders the keys, taking no space unless the comparator carries state
+	cmp: C
+}
+
+#[derive(Debug)]
+pub struct TreeNode<K,V,S=()> {
+	is_red: bool,
+	///Number of entries in this subtree
+	size: usize,
+	///Combined summary of every entry in this subtree
+	summary: S,
+	///Shared between versions, so path copying never clones keys or values
+	entry: Ptr<(K,V)>,
+	left: Ptr<Option<TreeNode<K,V,S>>>,
+	right: Ptr<Option<TreeNode<K,V,S>>>
+}
+
+trait HasColour {
+	fn is_red(&self) -> bool;
+}
+
+trait HasSize {
+	fn size(&self) -> usize;
+}
+
+impl<K,V,S> HasSize for Option<TreeNode<K,V,S>> {
+	fn size(&self) -> usize {
+		match *self {
+			None => 0,
+			Some(ref node) => node.size
+		}
+	}
+}
+
+impl<K,V,S> HasColour for Option<TreeNode<K,V,S>> {
+	fn is_red(&self) -> bool {
+		match *self {
+			None => false,
+			Some(ref node) => {
+				node.is_red
+			}
+		}
+	}
+}
+
+impl<K,V,S> TreeNode<K,V,S> where S: Summary<K,V> {
+	fn new(is_red: bool, entry: Ptr<(K,V)>, left: Ptr<Option<TreeNode<K,V,S>>>, right: Ptr<Option<TreeNode<K,V,S>>>) -> Ptr<Option<TreeNode<K,V,S>>> {
+		Ptr::new(Some(TreeNode{
+			is_red,
+			size: left.size() + right.size() + 1,
+			summary: summarise(&left, &entry, &right),
+			entry,
+			left,
+			right
+		}))
+	}
+
+	fn recolour(&self, is_red: bool, left: Ptr<Option<TreeNode<K,V,S>>>, right: Ptr<Option<TreeNode<K,V,S>>>) -> Ptr<Option<TreeNode<K,V,S>>> {
+		TreeNode::new(is_red, self.entry.clone(), left, right)
+	}
+
+	///Replaces a node with at most one child by that child
+	fn unlink(&self) -> RemovalResultRecursion<K,V,S> {
+		match (self.left.as_ref(), self.right.as_ref()) {
+			(Some(child), None) | (None, Some(child)) => {
+				//only child must be a red leaf, take over our black
+				RemovalResultRecursion::Standard(child.recolour(false, child.left.clone(), child.right.clone()))
+			},
+			(None, None) => {
+				if self.is_red {
+					RemovalResultRecursion::Standard(Ptr::new(None))
+				} else {
+					RemovalResultRecursion::Shortened(Ptr::new(None))
+				}
+			},
+			_ => panic!("Cannot unlink a node with two children")
+		}
+	}
+
+	///Takes this node out of its subtree
+	fn remove_self(&self) -> RemovalResultRecursion<K,V,S> {
+		match (self.left.as_ref(), self.right.as_ref()) {
+			(Some(_), Some(right)) => {
+				//replace with in-order successor
+				let (right_result, successor) = right.remove_min();
+				successor.fixup_right(self.is_red, self.left.clone(), right_result)
+			},
+			_ => self.unlink()
+		}
+	}
+
+	///Takes the smallest entry out of this subtree
+	fn remove_min(&self) -> Removal<'_,K,V,S> {
+		match *self.left {
+			Some(ref left) => {
+				let (left_result, removed) = left.remove_min();
+				(self.fixup_left(self.is_red, left_result, self.right.clone()), removed)
+			},
+			None => (self.unlink(), self)
+		}
+	}
+
+	///Takes the largest entry out of this subtree
+	fn remove_max(&self) -> Removal<'_,K,V,S> {
+		match *self.right {
+			Some(ref right) => {
+				let (right_result, removed) = right.remove_max();
+				(self.fixup_right(self.is_red, self.left.clone(), right_result), removed)
+			},
+			None => (self.unlink(), self)
+		}
+	}
+
+	///Rebuilds this node after an insertion into the left subtree
+	fn fixup_insert_left<'a>(&self, result: InsertionResultRecursion<'a,K,V,S>) -> InsertionResultRecursion<'a,K,V,S> {
+		match result {
+			InsertionResultRecursion::Failure(key, val, existing) => InsertionResultRecursion::Failure(key, val, existing),
+			InsertionResultRecursion::Replaced(left_child, old_node) => {
+				InsertionResultRecursion::Replaced(self.recolour(self.is_red, left_child, self.right.clone()), old_node)
+			},
+			InsertionResultRecursion::Standard(left_child) => {
+				if self.is_red() && left_child.is_red() {
+					//red
+					InsertionResultRecursion::DoubleRedLeft(left_child)
+				} else {
+					//black, or child still black
+					InsertionResultRecursion::Standard(self.recolour(self.is_red, left_child, self.right.clone()))
+				}
+			},
+			InsertionResultRecursion::DoubleRedLeft(left_grandchild) => {
+				assert!(!self.is_red());
+
+				if self.right.is_red() {
+					//     B (self)
+					//    / \
+					//   R   R
+					//  /
+					// R
+					
+					//recolour self and both children
+					let old_left = self.left.as_ref().as_ref().unwrap();
+					let old_right = self.right.as_ref().as_ref().unwrap();
+
+					let new_left = old_left.recolour(false, left_grandchild, old_left.right.clone());
+					let new_right = old_right.recolour(false, old_right.left.clone(), old_right.right.clone());
+
+					InsertionResultRecursion::Standard(self.recolour(true, new_left, new_right))
+				} else {
+					//     B (self)
+					//    / \
+					//   R   B
+					//  /
+					// R
+					
+					//reorder and recolour
+					let old_left = self.left.as_ref().as_ref().unwrap();
+					let new_right = self.recolour(true, old_left.right.clone(), self.right.clone());
+					InsertionResultRecursion::Standard(old_left.recolour(false, left_grandchild, new_right))
+				}
+			},
+			InsertionResultRecursion::DoubleRedRight(right_grandchild) => {
+				assert!(!self.is_red());
+
+				if self.right.is_red() {
+					//   B (self)
+					//  / \
+					// R   R
+					//  \
+					//   R
+					
+					// recolour self and both children
+					let old_left = self.left.as_ref().as_ref().unwrap();
+					let old_right = self.right.as_ref().as_ref().unwrap();
+
+					let new_left = old_left.recolour(false, old_left.left.clone(), right_grandchild);
+					let new_right = old_right.recolour(false, old_right.left.clone(), old_right.right.clone());
+
+					InsertionResultRecursion::Standard(self.recolour(true, new_left, new_right))
+				} else {
+					//   B (self)
+					//  / \
+					// R   B
+					//  \
+					//   R
+					
+					// reorder and recolour
+					let old_left = self.left.as_ref().as_ref().unwrap();
+					let old_grandchild = right_grandchild.as_ref().as_ref().unwrap();
+					
+					let new_left = old_left.recolour(true, old_left.left.clone(), old_grandchild.left.clone());
+					let new_right = self.recolour(true, old_grandchild.right.clone(), self.right.clone());
+					InsertionResultRecursion::Standard(old_grandchild.recolour(false, new_left, new_right))
+				}
+			}
+		}
+	}
+
+	///Rebuilds this node after an insertion into the right subtree
+	fn fixup_insert_right<'a>(&self, result: InsertionResultRecursion<'a,K,V,S>) -> InsertionResultRecursion<'a,K,V,S> {
+		match result {
+			InsertionResultRecursion::Failure(key, val, existing) => InsertionResultRecursion::Failure(key, val, existing),
+			InsertionResultRecursion::Replaced(right_child, old_node) => {
+				InsertionResultRecursion::Replaced(self.recolour(self.is_red, self.left.clone(), right_child), old_node)
+			},
+			InsertionResultRecursion::Standard(right_child) => {
+				if self.is_red() && right_child.is_red() {
+					//red
+					InsertionResultRecursion::DoubleRedRight(right_child)
+				} else {
+					//black, or child still black
+					InsertionResultRecursion::Standard(self.recolour(self.is_red, self.left.clone(), right_child))
+				}
+			},
+			InsertionResultRecursion::DoubleRedLeft(left_grandchild) => {
+				assert!(!self.is_red());
+
+				if self.left.is_red() {
+					//   B (self)
+					//  / \
+					// R   R
+					//    /
+					//   R
+					
+					// recolour self and both children
+					let old_left = self.left.as_ref().as_ref().unwrap();
+					let old_right = self.right.as_ref().as_ref().unwrap();
+
+					let new_left = old_left.recolour(false, old_left.left.clone(), old_left.right.clone());
+					let new_right = old_right.recolour(false, left_grandchild, old_right.right.clone());
+
+					InsertionResultRecursion::Standard(self.recolour(true, new_left, new_right))
+				} else {
+					//   B (self)
+					//  / \
+					// B   R
+					//    /
+					//   R
+					
+					// reorder and recolour
+					let old_right = self.right.as_ref().as_ref().unwrap();
+					let old_grandchild = left_grandchild.as_ref().as_ref().unwrap();
+					let new_left = self.recolour(true, self.left.clone(), old_grandchild.left.clone());
+					let new_right = old_right.recolour(true, old_grandchild.right.clone(), old_right.right.clone());
+					InsertionResultRecursion::Standard(old_grandchild.recolour(false, new_left, new_right))
+				}
+			},
+			InsertionResultRecursion::DoubleRedRight(right_grandchild) => {
+				assert!(!self.is_red());
+
+				if self.left.is_red() {
+					//   B (self)
+					//  / \
+					// R   R
+					//      \
+					//       R
+					
+					// recolour self and both children
+					let old_left = self.left.as_ref().as_ref().unwrap();
+					let old_right = self.right.as_ref().as_ref().unwrap();
+
+					let new_left = old_left.recolour(false, old_left.left.clone(), old_left.right.clone());
+					let new_right = old_right.recolour(false, old_right.left.clone(), right_grandchild);
+
+					InsertionResultRecursion::Standard(self.recolour(true, new_left, new_right))
+				} else {
+					//   B (self)
+					//  / \
+					// B   R
+					//      \
+					//       R
+					
+					// reorder and recolour
+					let old_right = self.right.as_ref().as_ref().unwrap();
+					let new_left = self.recolour(true, self.left.clone(), old_right.left.clone());
+					InsertionResultRecursion::Standard(old_right.recolour(false, new_left, right_grandchild))
+				}
+			}
+		}
+	}
+
+	///Rebuilds this node after a removal from the left subtree
+	fn fixup_left(&self, is_red: bool, result: RemovalResultRecursion<K,V,S>, right: Ptr<Option<TreeNode<K,V,S>>>) -> RemovalResultRecursion<K,V,S> {
+		match result {
+			RemovalResultRecursion::Standard(left) => RemovalResultRecursion::Standard(self.recolour(is_red, left, right)),
+			RemovalResultRecursion::Shortened(left) => self.rebalance_left(is_red, left, right)
+		}
+	}
+
+	///Rebuilds this node after a removal from the right subtree
+	fn fixup_right(&self, is_red: bool, left: Ptr<Option<TreeNode<K,V,S>>>, result: RemovalResultRecursion<K,V,S>) -> RemovalResultRecursion<K,V,S> {
+		match result {
+			RemovalResultRecursion::Standard(right) => RemovalResultRecursion::Standard(self.recolour(is_red, left, right)),
+			RemovalResultRecursion::Shortened(right) => self.rebalance_right(is_red, left, right)
+		}
+	}
+
+	///Left subtree is one black node shorter than the right subtree
+	fn rebalance_left(&self, is_red: bool, left: Ptr<Option<TreeNode<K,V,S>>>, right: Ptr<Option<TreeNode<K,V,S>>>) -> RemovalResultRecursion<K,V,S> {
+		if left.is_red() {
+			//blacken the short side
+			let old_left = left.as_ref().as_ref().unwrap();
+			let new_left = old_left.recolour(false, old_left.left.clone(), old_left.right.clone());
+			return RemovalResultRecursion::Standard(self.recolour(is_red, new_left, right));
+		}
+
+		let sibling = right.as_ref().as_ref().unwrap();
+
+		if sibling.is_red() {
+			//     B (self)
+			//    / \
+			//   -   R
+			//      / \
+			//     B   B
+			assert!(!is_red);
+
+			//rotate left, the red parent then absorbs the shortfall
+			match self.rebalance_left(true, left, sibling.left.clone()) {
+				RemovalResultRecursion::Standard(new_left) => {
+					RemovalResultRecursion::Standard(sibling.recolour(false, new_left, sibling.right.clone()))
+				},
+				RemovalResultRecursion::Shortened(_) => panic!("Unexpected recursion result")
+			}
+		} else if sibling.right.is_red() {
+			//     ? (self)
+			//    / \
+			//   -   B
+			//        \
+			//         R
+
+			//rotate left and recolour
+			let old_far = sibling.right.as_ref().as_ref().unwrap();
+			let new_left = self.recolour(false, left, sibling.left.clone());
+			let new_right = old_far.recolour(false, old_far.left.clone(), old_far.right.clone());
+			RemovalResultRecursion::Standard(sibling.recolour(is_red, new_left, new_right))
+		} else if sibling.left.is_red() {
+			//     ? (self)
+			//    / \
+			//   -   B
+			//      /
+			//     R
+
+			//rotate right then left and recolour
+			let old_near = sibling.left.as_ref().as_ref().unwrap();
+			let new_left = self.recolour(false, left, old_near.left.clone());
+			let new_right = sibling.recolour(false, old_near.right.clone(), sibling.right.clone());
+			RemovalResultRecursion::Standard(old_near.recolour(is_red, new_left, new_right))
+		} else {
+			//     ? (self)
+			//    / \
+			//   -   B
+			//      / \
+			//     B   B
+
+			//recolour sibling, pass the shortfall up if we were already black
+			let new_right = sibling.recolour(true, sibling.left.clone(), sibling.right.clone());
+			if is_red {
+				RemovalResultRecursion::Standard(self.recolour(false, left, new_right))
+			} else {
+				RemovalResultRecursion::Shortened(self.recolour(false, left, new_right))
+			}
+		}
+	}
+
+	///Right subtree is one black node shorter than the left subtree
+	fn rebalance_right(&self, is_red: bool, left: Ptr<Option<TreeNode<K,V,S>>>, right: Ptr<Option<TreeNode<K,V,S>>>) -> RemovalResultRecursion<K,V,S> {
+		if right.is_red() {
+			//blacken the short side
+			let old_right = right.as_ref().as_ref().unwrap();
+			let new_right = old_right.recolour(false, old_right.left.clone(), old_right.right.clone());
+			return RemovalResultRecursion::Standard(self.recolour(is_red, left, new_right));
+		}
+
+		let sibling = left.as_ref().as_ref().unwrap();
+
+		if sibling.is_red() {
+			//     B (self)
+			//    / \
+			//   R   -
+			//  / \
+			// B   B
+			assert!(!is_red);
+
+			//rotate right, the red parent then absorbs the shortfall
+			match self.rebalance_right(true, sibling.right.clone(), right) {
+				RemovalResultRecursion::Standard(new_right) => {
+					RemovalResultRecursion::Standard(sibling.recolour(false, sibling.left.clone(), new_right))
+				},
+				RemovalResultRecursion::Shortened(_) => panic!("Unexpected recursion result")
+			}
+		} else if sibling.left.is_red() {
+			//       ? (self)
+			//      / \
+			//     B   -
+			//    /
+			//   R
+
+			//rotate right and recolour
+			let old_far = sibling.left.as_ref().as_ref().unwrap();
+			let new_left = old_far.recolour(false, old_far.left.clone(), old_far.right.clone());
+			let new_right = self.recolour(false, sibling.right.clone(), right);
+			RemovalResultRecursion::Standard(sibling.recolour(is_red, new_left, new_right))
+		} else if sibling.right.is_red() {
+			//     ? (self)
+			//    / \
+			//   B   -
+			//    \
+			//     R
+
+			//rotate left then right and recolour
+			let old_near = sibling.right.as_ref().as_ref().unwrap();
+			let new_left = sibling.recolour(false, sibling.left.clone(), old_near.left.clone());
+			let new_right = self.recolour(false, old_near.right.clone(), right);
+			RemovalResultRecursion::Standard(old_near.recolour(is_red, new_left, new_right))
+		} else {
+			//     ? (self)
+			//    / \
+			//   B   -
+			//  / \
+			// B   B
+
+			//recolour sibling, pass the shortfall up if we were already black
+			let new_left = sibling.recolour(true, sibling.left.clone(), sibling.right.clone());
+			if is_red {
+				RemovalResultRecursion::Standard(self.recolour(false, new_left, right))
+			} else {
+				RemovalResultRecursion::Shortened(self.recolour(false, new_left, right))
+			}
+		}
+	}
+}
+
+impl<K,V,S> TreeNode<K,V,S> {
+	pub fn key(&self) -> &K {
+		&self.entry.0
+	}
+
+	pub fn val(&self) -> &V {
+		&self.entry.1
+	}
+
+	///Summary of every entry in this subtree
+	pub fn summary(&self) -> &S {
+		&self.summary
+	}
+}
+
+impl<K,V,S> HasColour for TreeNode<K,V,S> {
+	fn is_red(&self) -> bool {
+		self.is_red
+	}
+}
+
+enum InsertionResultRecursion<'a,K,V,S> {
+	///Key already present, hands back the rejected key and value
+	Failure(K, V, &'a TreeNode<K,V,S>),
+	///No additional steps necessary
+	Standard(Ptr<Option<TreeNode<K,V,S>>>),
+	///Key already present, value replaced in place
+	Replaced(Ptr<Option<TreeNode<K,V,S>>>, &'a TreeNode<K,V,S>),
+	///Child is red, new grandchild on left also red
+	DoubleRedLeft(Ptr<Option<TreeNode<K,V,S>>>),
+	///Child is red, new grandchild on right also red
+	DoubleRedRight(Ptr<Option<TreeNode<K,V,S>>>),
+}
+
+enum RemovalResultRecursion<K,V,S> {
+	///Black height of subtree unchanged
+	Standard(Ptr<Option<TreeNode<K,V,S>>>),
+	///Black height of subtree reduced by one
+	Shortened(Ptr<Option<TreeNode<K,V,S>>>),
+}
+
+enum UpdateResultRecursion<'a,K,V,S> {
+	///Key absent and left absent, nothing to rebuild
+	Unchanged,
+	///Entry added or its value replaced
+	Inserted(InsertionResultRecursion<'a,K,V,S>),
+	///Entry taken out
+	Removed(RemovalResultRecursion<K,V,S>),
+}
+
+///Returned by `Tree::try_insert` when the key is already present
+#[derive(Debug)]
+pub struct DuplicateKeyError<'a,K,V> {
+	///Key that was passed in
+	pub key: K,
+	///Value that was passed in
+	pub val: V,
+	///Value already stored under the key
+	pub existing: &'a V,
+}
+
+impl<'a,K,V> fmt::Display for DuplicateKeyError<'a,K,V> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "key already present in tree")
+	}
+}
+
+impl<'a,K,V> Error for DuplicateKeyError<'a,K,V> where K: fmt::Debug, V: fmt::Debug {}
+
+///Rebuilt subtree, and the node that was taken out of it
+type Removal<'a,K,V,S> = (RemovalResultRecursion<K,V,S>, &'a TreeNode<K,V,S>);
+
+impl<K,V,S,C> Default for Tree<K,V,S,C> where C: Comparator<K> + Default {
+	fn default() -> Tree<K,V,S,C> {
+		Tree {root: Ptr::new(None), cmp: C::default()}
+	}
+}
+
+///Another handle on the same version, sharing every node
+impl<K,V,S,C> Clone for Tree<K,V,S,C> where C: Clone {
+	fn clone(&self) -> Tree<K,V,S,C> {
+		Tree {root: self.root.clone(), cmp: self.cmp.clone()}
+	}
+}
+
+impl<K,V> Tree<K,V> where K: Ord {
+	///Empty tree without a summary, use `Tree::default` to pick one
+	pub fn new() -> Tree<K,V> {
+		Tree::default()
+	}
+}
+
+impl<K,V,C> Tree<K,V,(),C> where C: Comparator<K> + Clone {
+	///Empty tree ordering its keys by the given comparator instead of `Ord`
+	pub fn with_comparator(cmp: C) -> Tree<K,V,(),C> {
+		Tree {root: Ptr::new(None), cmp}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Empty tree keeping the summary `S`, ordering its keys by the given comparator
+	pub fn with_comparator_and_summary(cmp: C) -> Tree<K,V,S,C> {
+		Tree {root: Ptr::new(None), cmp}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+
+	///Number of entries, in constant time
+	pub fn len(&self) -> usize {
+		self.root.size()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.root.is_none()
+	}
+
+	pub fn find<Q>(&self, search_key: &Q) -> Option<&V> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		let mut current = self.root.as_ref();
+		
+		loop {
+			match *current {
+				None => return None,
+				Some(ref node) => {
+					match self.cmp.compare(search_key, node.key().borrow()) {
+						Ordering::Less => current = node.left.as_ref(),
+						Ordering::Greater => current = node.right.as_ref(),
+						Ordering::Equal => return Some(node.val())
+					}
+				}
+			};
+			/*match current {
+				&None => return None,
+				&Some(ref node) => {
+					match search_key.cmp(node.key().borrow()) {
+						Ordering::Less => current = node.left.as_ref(),
+						Ordering::Greater => current = node.right.as_ref(),
+						Ordering::Equal => return Some(node.val())
+					}
+				}
+			};*/
+		}
+	}
+
+	/*fn is_red(&self) -> bool {
+		match self {
+			&None => false,
+			&Some(ref node) => {
+				node.is_red
+			}
+		}
+	}*/
+
+	pub fn insert(&self, key: K, val: V) -> Option<Tree<K,V,S,C>> {
+		self.try_insert(key, val).ok()
+	}
+
+	pub fn try_insert(&self, key: K, val: V) -> Result<Tree<K,V,S,C>, DuplicateKeyError<'_,K,V>> {
+		match Self::rec_insert(&self.cmp, key, val, &self.root, false) {
+			InsertionResultRecursion::Failure(key, val, existing) => Err(DuplicateKeyError {key, val, existing: existing.val()}),
+			InsertionResultRecursion::Standard(root) => Ok(self.with_root(root)),
+			_ => panic!("Unexpected recursion result")
+		}
+	}
+
+	///Inserts the key, or replaces its value if already present, returning the old value
+	pub fn insert_or_replace(&self, key: K, val: V) -> (Tree<K,V,S,C>, Option<V>) where V: Clone {
+		match Self::rec_insert(&self.cmp, key, val, &self.root, true) {
+			InsertionResultRecursion::Standard(root) => (self.with_root(root), None),
+			InsertionResultRecursion::Replaced(root, old_node) => (Tree {root, cmp: self.cmp.clone()}, Some(old_node.val().clone())),
+			_ => panic!("Unexpected recursion result")
+		}
+	}
+
+	///Inserts, modifies or removes the entry for a key in a single path copy.
+	///The closure sees the current value if any, and returns the new value or None to leave the key absent.
+	pub fn update<F>(&self, key: K, f: F) -> Tree<K,V,S,C> where F: FnOnce(Option<&V>) -> Option<V> {
+		match Self::rec_update(&self.cmp, key, f, &self.root) {
+			UpdateResultRecursion::Unchanged => self.clone(),
+			UpdateResultRecursion::Inserted(InsertionResultRecursion::Standard(root)) => self.with_root(root),
+			UpdateResultRecursion::Inserted(InsertionResultRecursion::Replaced(root, _)) => Tree {root, cmp: self.cmp.clone()},
+			UpdateResultRecursion::Removed(RemovalResultRecursion::Standard(root)) | UpdateResultRecursion::Removed(RemovalResultRecursion::Shortened(root)) => self.with_root(root),
+			_ => panic!("Unexpected recursion result")
+		}
+	}
+
+	///Wraps a rebuilt root with this tree's comparator, blackening it if necessary
+	fn with_root(&self, root: Ptr<Option<TreeNode<K,V,S>>>) -> Tree<K,V,S,C> {
+		if root.is_red() {
+			//red
+			let old_node = root.as_ref().as_ref().unwrap();
+			Tree {root: old_node.recolour(false, old_node.left.clone(), old_node.right.clone()), cmp: self.cmp.clone()}
+		} else {
+			//black
+			Tree {root, cmp: self.cmp.clone()}
+		}
+	}
+
+	pub fn remove<Q>(&self, key: &Q) -> Option<(Tree<K,V,S,C>, V)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, V: Clone {
+		match Self::rec_remove(&self.cmp, key, &self.root) {
+			None => None,
+			Some((RemovalResultRecursion::Standard(root), removed)) | Some((RemovalResultRecursion::Shortened(root), removed)) => {
+				Some((self.with_root(root), removed.val().clone()))
+			}
+		}
+	}
+
+	/*fn sibling<'a>(current: &'a Ptr<Option<TreeNode<K,V,S>>>, parent: &'a Ptr<Option<TreeNode<K,V,S>>>) -> &'a Ptr<Option<TreeNode<K,V,S>>> {
+		match parent.as_ref() {
+			&None => panic!("assertion failure"),
+			&Some(ref p) => {
+				if ptr_eq(p.left.as_ref(), current.as_ref()) {
+					&p.right
+				} else if ptr_eq(p.right.as_ref(), current.as_ref()) {
+					&p.left
+				} else {
+					panic!("assertion failure")
+				}
+			}
+		}
+	}*/
+
+	fn rec_insert<'a>(cmp: &C, key: K, val: V, current: &'a Ptr<Option<TreeNode<K,V,S>>>, replace: bool) -> InsertionResultRecursion<'a,K,V,S>{
+		match **current {
+			None => {
+				//insert here, the empty link being replaced serves as both children
+				InsertionResultRecursion::Standard(TreeNode::new(true, Ptr::new((key, val)), current.clone(), current.clone()))
+			},
+			Some(ref node) => {
+				match cmp.compare(&key, node.key()) {
+					Ordering::Less => node.fixup_insert_left(Self::rec_insert(cmp, key, val, &node.left, replace)),
+					Ordering::Greater => node.fixup_insert_right(Self::rec_insert(cmp, key, val, &node.right, replace)),
+					Ordering::Equal => {
+						if replace {
+							//same colour and children, so no rebalancing needed
+							InsertionResultRecursion::Replaced(TreeNode::new(node.is_red, Ptr::new((key, val)), node.left.clone(), node.right.clone()), node)
+						} else {
+							InsertionResultRecursion::Failure(key, val, node)
+						}
+					}
+				}
+			}
+		}
+	}
+
+	fn rec_remove<'a,Q>(cmp: &C, key: &Q, current: &'a Ptr<Option<TreeNode<K,V,S>>>) -> Option<Removal<'a,K,V,S>> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		match **current {
+			None => None,
+			Some(ref node) => {
+				match cmp.compare(key, node.key().borrow()) {
+					Ordering::Less => {
+						Self::rec_remove(cmp, key, &node.left).map(|(left_result, removed)| {
+							(node.fixup_left(node.is_red, left_result, node.right.clone()), removed)
+						})
+					},
+					Ordering::Greater => {
+						Self::rec_remove(cmp, key, &node.right).map(|(right_result, removed)| {
+							(node.fixup_right(node.is_red, node.left.clone(), right_result), removed)
+						})
+					},
+					Ordering::Equal => Some((node.remove_self(), node))
+				}
+			}
+		}
+	}
+
+	fn rec_update<'a,F>(cmp: &C, key: K, f: F, current: &'a Ptr<Option<TreeNode<K,V,S>>>) -> UpdateResultRecursion<'a,K,V,S> where F: FnOnce(Option<&V>) -> Option<V> {
+		match **current {
+			None => {
+				match f(None) {
+					None => UpdateResultRecursion::Unchanged,
+					Some(val) => UpdateResultRecursion::Inserted(InsertionResultRecursion::Standard(TreeNode::new(true, Ptr::new((key, val)), current.clone(), current.clone())))
+				}
+			},
+			Some(ref node) => {
+				match cmp.compare(&key, node.key()) {
+					Ordering::Less => {
+						match Self::rec_update(cmp, key, f, &node.left) {
+							UpdateResultRecursion::Unchanged => UpdateResultRecursion::Unchanged,
+							UpdateResultRecursion::Inserted(result) => UpdateResultRecursion::Inserted(node.fixup_insert_left(result)),
+							UpdateResultRecursion::Removed(result) => UpdateResultRecursion::Removed(node.fixup_left(node.is_red, result, node.right.clone()))
+						}
+					},
+					Ordering::Greater => {
+						match Self::rec_update(cmp, key, f, &node.right) {
+							UpdateResultRecursion::Unchanged => UpdateResultRecursion::Unchanged,
+							UpdateResultRecursion::Inserted(result) => UpdateResultRecursion::Inserted(node.fixup_insert_right(result)),
+							UpdateResultRecursion::Removed(result) => UpdateResultRecursion::Removed(node.fixup_right(node.is_red, node.left.clone(), result))
+						}
+					},
+					Ordering::Equal => {
+						match f(Some(node.val())) {
+							//same colour and children, so no rebalancing needed
+							Some(val) => UpdateResultRecursion::Inserted(InsertionResultRecursion::Replaced(TreeNode::new(node.is_red, Ptr::new((key, val)), node.left.clone(), node.right.clone()), node)),
+							None => UpdateResultRecursion::Removed(node.remove_self())
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::toplevel_ref_arg, clippy::needless_borrow)]
+mod tests {
+	use super::*;
+	use super::HasColour;
+
+	#[test]
+	fn test_find(){
+		let tree = Tree{cmp: Natural, root: Ptr::new(Some(TreeNode {
+			is_red: false,
+			size: 2,
+			summary: (),
+			entry: Ptr::new((1, ())),
+			left: Ptr::new(None),
+			right: Ptr::new(Some(TreeNode{
+				is_red: true,
+				size: 1,
+				summary: (),
+				entry: Ptr::new((6, ())),
+				left: Ptr::new(None),
+				right: Ptr::new(None)
+			}))
+		}))};
+
+		assert_eq!(tree.find(&6), Some(&()));
+		assert_eq!(tree.find(&1), Some(&()));
+		assert_eq!(tree.find(&12), None);
+	}
+
+	#[test]
+	fn test_insert_case1(){
+		let start = Tree::new();
+		let test = start.insert(5, ()).unwrap();
+		//  5
+		assert_eq!(test.root.is_some(), true);
+
+		let test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), false);
+		assert_eq!(test_root.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case2_left() {
+		let start = Tree::new().insert(5, ()).unwrap();
+		let test = start.insert(4, ()).unwrap();
+		//    5
+		//   /
+		//  4
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), false);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), true);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case2_right() {
+		let start = Tree::new().insert(5, ()).unwrap();
+		let test = start.insert(6, ()).unwrap();
+		//  5
+		//   \
+		//    6
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), false);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), true);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case5_left() {
+		//      B
+		//     /
+		//    R
+		//   /
+		//  R
+		let start = Tree::new().insert(6, ()).unwrap().insert(5, ()).unwrap();
+		let test = start.insert(4, ()).unwrap();
+		//    5
+		//   / \
+		//  4   6
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), true);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), true);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case5_right() {
+		//  B
+		//   \
+		//    R
+		//     \
+		//      R
+		let start = Tree::new().insert(4, ()).unwrap().insert(5, ()).unwrap();
+		let test = start.insert(6, ()).unwrap();
+		//    5
+		//   / \
+		//  4   6
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), true);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), true);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case4_left() {
+		//    B
+		//   /
+		//  R
+		//   \
+		//    R
+		let start = Tree::new().insert(6, ()).unwrap().insert(4, ()).unwrap();
+		let test = start.insert(5, ()).unwrap();
+		//    5
+		//   / \
+		//  4   6
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), true);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), true);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case4_right() {
+		//  B
+		//   \
+		//    R
+		//   /
+		//  R
+		let start = Tree::new().insert(4, ()).unwrap().insert(6, ()).unwrap();
+		let test = start.insert(5, ()).unwrap();
+		//    5
+		//   / \
+		//  4   6
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), true);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), true);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case3_left_a() {
+		//      B
+		//     / \
+		//    R   R
+		//   /
+		//  R
+		let start = Tree::new().insert(5, ()).unwrap().insert(4, ()).unwrap().insert(6, ()).unwrap();
+		let test = start.insert(3, ()).unwrap();
+		//      5
+		//     / \
+		//    4   6
+		//   /
+		//  3
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), false);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), true);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), false);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+
+		let ref test_left_left = test_left.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left_left.is_red(), true);
+		assert_eq!(*test_left_left.key(), 3);
+		assert_eq!(test_left_left.left.is_some(), false);
+		assert_eq!(test_left_left.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case3_left_b() {
+		//    B
+		//   / \
+		//  R   R
+		//   \
+		//    R
+		let start = Tree::new().insert(5, ()).unwrap().insert(3, ()).unwrap().insert(6, ()).unwrap();
+		let test = start.insert(4, ()).unwrap();
+		//    5
+		//   / \
+		//  3   6
+		//   \
+		//    4
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), false);
+		assert_eq!(*test_left.key(), 3);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), true);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), false);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), false);
+
+		let ref test_left_right = test_left.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_left_right.is_red(), true);
+		assert_eq!(*test_left_right.key(), 4);
+		assert_eq!(test_left_right.left.is_some(), false);
+		assert_eq!(test_left_right.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case3_right_a() {
+		//    B
+		//   / \
+		//  R   R
+		//     /
+		//    R
+		let start = Tree::new().insert(5, ()).unwrap().insert(4, ()).unwrap().insert(7, ()).unwrap();
+		let test = start.insert(6, ()).unwrap();
+		//      5
+		//     / \
+		//    4   7
+		//       /
+		//      6
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), false);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), false);
+		assert_eq!(*test_right.key(), 7);
+		assert_eq!(test_right.left.is_some(), true);
+		assert_eq!(test_right.right.is_some(), false);
+
+		let ref test_right_left = test_right.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_right_left.is_red(), true);
+		assert_eq!(*test_right_left.key(), 6);
+		assert_eq!(test_right_left.left.is_some(), false);
+		assert_eq!(test_right_left.right.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_case3_right_b() {
+		//    B
+		//   / \
+		//  R   R
+		//       \
+		//        R
+		let start = Tree::new().insert(5, ()).unwrap().insert(4, ()).unwrap().insert(6, ()).unwrap();
+		let test = start.insert(7, ()).unwrap();
+		//      5
+		//     / \
+		//    4   6
+		//         \
+		//          7
+		assert_eq!(test.root.is_some(), true);
+
+		let ref test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), true);
+		assert_eq!(test_root.right.is_some(), true);
+
+		let ref test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), false);
+		assert_eq!(*test_left.key(), 4);
+		assert_eq!(test_left.left.is_some(), false);
+		assert_eq!(test_left.right.is_some(), false);
+
+		let ref test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), false);
+		assert_eq!(*test_right.key(), 6);
+		assert_eq!(test_right.left.is_some(), false);
+		assert_eq!(test_right.right.is_some(), true);
+
+		let ref test_right_right = test_right.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right_right.is_red(), true);
+		assert_eq!(*test_right_right.key(), 7);
+		assert_eq!(test_right_right.left.is_some(), false);
+		assert_eq!(test_right_right.right.is_some(), false);
+	}
+
+	///Tree mapping each key to ten times itself
+	pub fn build<I: IntoIterator<Item=i32>>(keys: I) -> Tree<i32,i32> {
+		keys.into_iter().fold(Tree::new(), |tree, key| tree.insert(key, key * 10).unwrap())
+	}
+
+	///Feeds the step a seeded xorshift sequence of keys in `0..keys`, each with a coin flip
+	pub fn random_steps<F>(seed: u32, keys: u32, steps: usize, mut step: F) where F: FnMut(usize, i32, bool) {
+		let mut state = seed;
+		for index in 0..steps {
+			state ^= state << 13;
+			state ^= state >> 17;
+			state ^= state << 5;
+			step(index, (state % keys) as i32, state & 0x100 == 0);
+		}
+	}
+
+	///Panics with every red-black violation found
+	pub fn check_tree(tree: &Tree<i32,i32>) {
+		let violations = tree.check_invariants();
+		assert!(violations.is_empty(), "{}", violations.iter().map(|violation| violation.to_string()).collect::<Vec<_>>().join(", "));
+	}
+
+	///Empty link for building trees by hand with `node`
+	pub fn nil() -> Ptr<Option<TreeNode<i32,i32>>> {
+		Ptr::new(None)
+	}
+
+	///Node mapping the key to itself, for building trees by hand whatever shape they break
+	pub fn node(is_red: bool, key: i32, left: Ptr<Option<TreeNode<i32,i32>>>, right: Ptr<Option<TreeNode<i32,i32>>>) -> Ptr<Option<TreeNode<i32,i32>>> {
+		TreeNode::new(is_red, Ptr::new((key, key)), left, right)
+	}
+
+	#[test]
+	fn test_insert_rotation_below_red() {
+		//    10B
+		//   /   \
+		//  5B   20R
+		//      /   \
+		//    15B   30B
+		//          /
+		//        25R
+		let right = node(true, 20, node(false, 15, nil(), nil()), node(false, 30, node(true, 25, nil(), nil()), nil()));
+		let start = Tree {cmp: Natural, root: node(false, 10, node(false, 5, nil(), nil()), right)};
+		check_tree(&start);
+
+		//rotating at 30 hands a black subtree root back up to red 20, which is not a double red
+		let test = start.insert(22, 22).unwrap();
+		check_tree(&test);
+		let test_right = test.root.as_ref().as_ref().unwrap().right.as_ref().as_ref().unwrap();
+		assert!(test_right.is_red());
+		assert_eq!(*test_right.key(), 20);
+		assert!(test.keys().cloned().eq(vec![5, 10, 15, 20, 22, 25, 30]));
+	}
+
+	#[test]
+	fn test_remove_missing() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap();
+		assert!(start.remove(&6).is_none());
+		assert!(Tree::<i32,i32>::new().remove(&6).is_none());
+	}
+
+	#[test]
+	fn test_insert_reuses_empty_links() {
+		let empty = Tree::new();
+		let leaf = |tree: &Tree<i32,i32>| {
+			let root = tree.root.as_ref().as_ref().unwrap();
+			assert!(Ptr::ptr_eq(&root.left, &empty.root));
+			assert!(Ptr::ptr_eq(&root.right, &empty.root));
+		};
+		leaf(&empty.insert(1, 10).unwrap());
+		leaf(&empty.update(1, |_| Some(10)));
+		leaf(&empty.insert_or_replace(1, 10).0);
+	}
+
+	#[test]
+	fn test_clone_shares_root() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap();
+		let copy = start.clone();
+		assert!(Ptr::ptr_eq(&start.root, &copy.root));
+		assert!(Ptr::ptr_eq(&start.update(6, |_| None).root, &start.root));
+	}
+
+	#[test]
+	fn test_remove_red_leaf() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap().insert(6, 60).unwrap();
+		let (test, val) = start.remove(&4).unwrap();
+		//  5
+		//   \
+		//    6
+		assert_eq!(val, 40);
+		check_tree(&test);
+
+		let test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 5);
+		assert_eq!(test_root.left.is_some(), false);
+		assert_eq!(test_root.right.is_some(), true);
+
+		//original untouched
+		assert_eq!(start.find(&4), Some(&40));
+		check_tree(&start);
+	}
+
+	#[test]
+	fn test_remove_black_leaf() {
+		//      5
+		//     / \
+		//    4   6
+		//   /
+		//  3
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap().insert(6, 60).unwrap().insert(3, 30).unwrap();
+		let (test, val) = start.remove(&6).unwrap();
+		//    4
+		//   / \
+		//  3   5
+		assert_eq!(val, 60);
+		check_tree(&test);
+
+		let test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.is_red(), false);
+		assert_eq!(*test_root.key(), 4);
+
+		let test_left = test_root.left.as_ref().as_ref().unwrap();
+		assert_eq!(test_left.is_red(), false);
+		assert_eq!(*test_left.key(), 3);
+
+		let test_right = test_root.right.as_ref().as_ref().unwrap();
+		assert_eq!(test_right.is_red(), false);
+		assert_eq!(*test_right.key(), 5);
+	}
+
+	#[test]
+	fn test_remove_root() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap().insert(6, 60).unwrap();
+		let (test, val) = start.remove(&5).unwrap();
+		//    6
+		//   /
+		//  4
+		assert_eq!(val, 50);
+		check_tree(&test);
+
+		let test_root = test.root.as_ref().as_ref().unwrap();
+		assert_eq!(*test_root.key(), 6);
+		assert_eq!(test.find(&5), None);
+		assert_eq!(test.find(&4), Some(&40));
+
+		let (test, _) = test.remove(&6).unwrap();
+		let (test, _) = test.remove(&4).unwrap();
+		assert_eq!(test.root.is_some(), false);
+	}
+
+	#[test]
+	fn test_insert_remove_sequence() {
+		let mut tree = Tree::new();
+		let mut present = [false; 200];
+
+		random_steps(0x2545_f491, 200, 4000, |_, key, insert| {
+			if insert {
+				match tree.insert(key, key * 10) {
+					Some(next) => {
+						assert!(!present[key as usize]);
+						present[key as usize] = true;
+						tree = next;
+					},
+					None => assert!(present[key as usize])
+				}
+			} else {
+				match tree.remove(&key) {
+					Some((next, val)) => {
+						assert!(present[key as usize]);
+						assert_eq!(val, key * 10);
+						present[key as usize] = false;
+						tree = next;
+					},
+					None => assert!(!present[key as usize])
+				}
+			}
+			check_tree(&tree);
+		});
+
+		assert_eq!(tree.len(), present.iter().filter(|&&is_present| is_present).count());
+		for (key, &is_present) in present.iter().enumerate() {
+			assert_eq!(tree.find(&(key as i32)).is_some(), is_present);
+		}
+	}
+
+	#[test]
+	fn test_try_insert_duplicate() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap();
+
+		let err = start.try_insert(4, 41).err().unwrap();
+		assert_eq!(err.key, 4);
+		assert_eq!(err.val, 41);
+		assert_eq!(err.existing, &40);
+		assert_eq!(err.to_string(), "key already present in tree");
+
+		let test = start.try_insert(6, 60).unwrap();
+		assert_eq!(test.find(&6), Some(&60));
+		check_tree(&test);
+	}
+
+	#[test]
+	fn test_insert_or_replace() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap().insert(6, 60).unwrap();
+
+		let (test, old) = start.insert_or_replace(4, 41);
+		assert_eq!(old, Some(40));
+		assert_eq!(test.find(&4), Some(&41));
+		assert_eq!(start.find(&4), Some(&40));
+		check_tree(&test);
+
+		//unchanged shape, untouched subtree shared
+		let test_root = test.root.as_ref().as_ref().unwrap();
+		let start_root = start.root.as_ref().as_ref().unwrap();
+		assert!(Ptr::ptr_eq(&test_root.right, &start_root.right));
+
+		let (test, old) = test.insert_or_replace(3, 30);
+		assert_eq!(old, None);
+		assert_eq!(test.find(&3), Some(&30));
+		check_tree(&test);
+	}
+
+	#[test]
+	fn test_update() {
+		let start = Tree::new().insert(5, 50).unwrap().insert(4, 40).unwrap().insert(6, 60).unwrap();
+
+		//modify in place
+		let test = start.update(4, |val| val.map(|val| val + 1));
+		assert_eq!(test.find(&4), Some(&41));
+		assert_eq!(start.find(&4), Some(&40));
+		check_tree(&test);
+
+		//insert when absent
+		let test = test.update(3, |val| { assert_eq!(val, None); Some(30) });
+		assert_eq!(test.find(&3), Some(&30));
+		assert_eq!(test.len(), 4);
+		check_tree(&test);
+
+		//delete
+		let test = test.update(5, |val| { assert_eq!(val, Some(&50)); None });
+		assert_eq!(test.find(&5), None);
+		assert_eq!(test.len(), 3);
+		check_tree(&test);
+
+		//absent and left absent gives back the same version
+		let same = test.update(7, |_| None);
+		assert!(Ptr::ptr_eq(&same.root, &test.root));
+	}
+
+	#[test]
+	fn test_update_sequence() {
+		let mut tree = Tree::new();
+		let mut counts = [0; 100];
+
+		random_steps(0x1b87_3593, 100, 4000, |_, key, _| {
+			//count up, dropping the key again on every third hit
+			tree = tree.update(key, |val| {
+				let next = val.cloned().unwrap_or(0) + 1;
+				if next % 3 == 0 { None } else { Some(next) }
+			});
+			counts[key as usize] = (counts[key as usize] + 1) % 3;
+			check_tree(&tree);
+		});
+
+		for (key, &count) in counts.iter().enumerate() {
+			assert_eq!(tree.find(&(key as i32)).cloned().unwrap_or(0), count);
+		}
+	}
+
+	#[test]
+	fn test_non_copy_entries() {
+		let start = Tree::new()
+			.insert("b".to_owned(), vec![2; 100]).unwrap()
+			.insert("a".to_owned(), vec![1; 100]).unwrap();
+		let test = start.insert("c".to_owned(), vec![3; 100]).unwrap();
+
+		assert_eq!(test.find("a").map(|v| v[0]), Some(1));
+		assert_eq!(test.find("c").map(|v| v.len()), Some(100));
+		assert_eq!(start.find("c"), None);
+
+		//copied root refers to the same entry
+		let test_root = test.root.as_ref().as_ref().unwrap();
+		let start_root = start.root.as_ref().as_ref().unwrap();
+		assert_eq!(test_root.key(), "b");
+		assert!(Ptr::ptr_eq(&test_root.entry, &start_root.entry));
+
+		let (test, val) = test.remove("b").unwrap();
+		assert_eq!(val, vec![2; 100]);
+		assert_eq!(test.find("b"), None);
+	}
+
+	#[cfg(feature = "sync")]
+	#[test]
+	fn test_shared_between_threads() {
+		use std::thread;
+
+		fn assert_send_sync<T: Send + Sync>() {}
+		assert_send_sync::<Tree<String,Vec<u8>>>();
+
+		let mut tree = Tree::new();
+		for key in 0..100 {
+			tree = tree.insert(key, key * 10).unwrap();
+		}
+
+		let readers: Vec<_> = (0..4).map(|offset| {
+			let version = tree.clone();
+			thread::spawn(move || {
+				(0..100).filter(|key| key % 4 == offset).all(|key| version.find(&key) == Some(&(key * 10)))
+			})
+		}).collect();
+
+		//writer publishes a new version while readers hold the old one
+		let (next, _) = tree.remove(&50).unwrap();
+		assert_eq!(next.find(&50), None);
+
+		for reader in readers {
+			assert!(reader.join().unwrap());
+		}
+	}
+}
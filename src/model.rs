@@ -0,0 +1,455 @@
+//!Differential testing of `Tree` against `BTreeMap`, behind the `model-check` feature.
+//!Random operation sequences run against both, checking results and invariants after every step.
+//!A failing sequence is shrunk to a short reproduction that can be replayed with `run`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{Tree, TreeTransient};
+
+///Small seedable generator (xorshift64*), so a failing seed replays the same sequence anywhere
+#[derive(Debug, Clone)]
+pub struct Rng {
+	state: u64
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Rng {
+		//all-zero state is a fixed point of xorshift
+		Rng {state: if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed }}
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state ^= self.state >> 12;
+		self.state ^= self.state << 25;
+		self.state ^= self.state >> 27;
+		self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+	}
+
+	///Uniform value in `0..bound`
+	pub fn below(&mut self, bound: u32) -> u32 {
+		(((self.next_u64() >> 32) * u64::from(bound)) >> 32) as u32
+	}
+}
+
+///Tree version alongside the model it should match
+type Version = (Tree<i32,i32>, BTreeMap<i32,i32>);
+
+///Single step of a sequence, applied to the current version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+	///`insert`, which must fail exactly when the key is present
+	Insert(i32, i32),
+	///`insert_or_replace`, handing back the old value
+	Replace(i32, i32),
+	///`update` setting the value, or removing the key for None
+	Update(i32, Option<i32>),
+	Remove(i32),
+	PopFirst,
+	PopLast,
+	///Lookup without making a new version
+	Find(i32),
+	///`split` at the key, then `join` the halves back around it with the given value
+	Split(i32, i32),
+	///`union` with an earlier version, keeping the larger value for keys in both
+	Union(usize),
+	///`intersection` with an earlier version, keeping the larger value
+	Intersection(usize),
+	///`difference` with an earlier version
+	Difference(usize),
+	///`range` over the keys between the two, in both directions
+	Range(i32, i32),
+	Rank(i32),
+	Select(usize),
+	Floor(i32),
+	Ceiling(i32),
+	///Rebuilds the version with `from_sorted_iter`
+	FromSorted,
+	///Sets the value, or removes the key for None, through a `TreeTransient` of the version
+	Transient(i32, Option<i32>),
+	///Switch back to an earlier version, wrapping around the number made so far
+	Revisit(usize),
+}
+
+///Step at which the tree and the model disagreed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+	pub step: usize,
+	pub op: Op,
+	pub message: String,
+}
+
+impl fmt::Display for Failure {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "step {} ({:?}): {}", self.step, self.op, self.message)
+	}
+}
+
+///Shrunk failing sequence along with the seed that first produced it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+	pub seed: u64,
+	pub ops: Vec<Op>,
+	pub failure: Failure,
+}
+
+impl fmt::Display for Counterexample {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "seed {} fails at {}, shrunk to:", self.seed, self.failure)?;
+		for op in &self.ops {
+			writeln!(f, "\t{:?}", op)?;
+		}
+		Ok(())
+	}
+}
+
+///Random sequence of operations on keys in `0..keys`, small enough that keys keep colliding
+pub fn generate(rng: &mut Rng, steps: usize, keys: u32) -> Vec<Op> {
+	(0..steps).map(|_| {
+		let key = rng.below(keys) as i32;
+		let val = rng.below(1000) as i32;
+		match rng.below(120) {
+			0..=24 => Op::Insert(key, val),
+			25..=32 => Op::Replace(key, val),
+			33..=40 => Op::Update(key, Some(val)),
+			41..=44 => Op::Update(key, None),
+			45..=59 => Op::Remove(key),
+			60..=63 => Op::PopFirst,
+			64..=67 => Op::PopLast,
+			68..=75 => Op::Find(key),
+			76..=79 => Op::Split(key, val),
+			80..=82 => Op::Union(rng.below(steps as u32) as usize),
+			83..=85 => Op::Intersection(rng.below(steps as u32) as usize),
+			86..=88 => Op::Difference(rng.below(steps as u32) as usize),
+			89..=92 => Op::Range(key, rng.below(keys) as i32),
+			93..=95 => Op::Rank(key),
+			//a little past the end, so missing indices come up too
+			96..=98 => Op::Select(rng.below(keys + 2) as usize),
+			99..=101 => Op::Floor(key),
+			102..=104 => Op::Ceiling(key),
+			105..=106 => Op::FromSorted,
+			107..=110 => Op::Transient(key, Some(val)),
+			111..=113 => Op::Transient(key, None),
+			_ => Op::Revisit(rng.below(steps as u32) as usize)
+		}
+	}).collect()
+}
+
+///Compares every entry, the length, both ends and the invariants of a version with its model
+fn compare(tree: &Tree<i32,i32>, model: &BTreeMap<i32,i32>) -> Result<(), String> {
+	let violations = tree.check_invariants();
+	if !violations.is_empty() {
+		let messages: Vec<String> = violations.iter().map(|violation| violation.to_string()).collect();
+		return Err(format!("invariants broken: {}", messages.join(", ")));
+	}
+	if tree.len() != model.len() {
+		return Err(format!("length {} but model has {}", tree.len(), model.len()));
+	}
+	if !tree.iter().eq(model.iter()) {
+		return Err(format!("entries {:?} but model has {:?}", tree.iter().collect::<Vec<_>>(), model));
+	}
+	if tree.first() != model.iter().next() || tree.last() != model.iter().next_back() {
+		return Err(format!("ends {:?}..{:?} do not match the model", tree.first(), tree.last()));
+	}
+	Ok(())
+}
+
+///Applies one operation to a version and its model, returning the new version if one was made.
+///Operations combining two versions pick the other one from those made so far.
+fn apply(op: Op, tree: &Tree<i32,i32>, model: &BTreeMap<i32,i32>, versions: &[Version]) -> Result<Option<Version>, String> {
+	let mut next = model.clone();
+	let tree = match op {
+		Op::Insert(key, val) => {
+			let present = model.contains_key(&key);
+			match tree.insert(key, val) {
+				Some(_) if present => return Err("inserted a key that was already present".to_owned()),
+				None if !present => return Err("refused a key that was absent".to_owned()),
+				None => return Ok(None),
+				Some(tree) => {
+					next.insert(key, val);
+					tree
+				}
+			}
+		},
+		Op::Replace(key, val) => {
+			let (tree, old) = tree.insert_or_replace(key, val);
+			let expected = next.insert(key, val);
+			if old != expected {
+				return Err(format!("replaced {:?} but model had {:?}", old, expected));
+			}
+			tree
+		},
+		Op::Update(key, val) => {
+			let mut seen = None;
+			let tree = tree.update(key, |old| {
+				seen = Some(old.cloned());
+				val
+			});
+			let expected = match val {
+				Some(val) => next.insert(key, val),
+				None => next.remove(&key)
+			};
+			if seen != Some(expected) {
+				return Err(format!("closure saw {:?} but model had {:?}", seen, expected));
+			}
+			tree
+		},
+		Op::Remove(key) => {
+			match (tree.remove(&key), next.remove(&key)) {
+				(None, None) => return Ok(None),
+				(Some((tree, old)), Some(expected)) if old == expected => tree,
+				(result, expected) => return Err(format!("removed {:?} but model had {:?}", result.map(|(_, old)| old), expected))
+			}
+		},
+		Op::PopFirst | Op::PopLast => {
+			let expected = if op == Op::PopFirst { model.iter().next() } else { model.iter().next_back() };
+			let popped = if op == Op::PopFirst { tree.pop_first() } else { tree.pop_last() };
+			match (popped, expected) {
+				(None, None) => return Ok(None),
+				(Some((tree, entry)), Some(expected)) if (&entry.0, &entry.1) == expected => {
+					next.remove(&entry.0);
+					tree
+				},
+				(popped, expected) => return Err(format!("popped {:?} but model had {:?}", popped.map(|(_, entry)| entry), expected))
+			}
+		},
+		Op::Find(key) => {
+			if tree.find(&key) != model.get(&key) {
+				return Err(format!("found {:?} but model had {:?}", tree.find(&key), model.get(&key)));
+			}
+			return Ok(None);
+		},
+		Op::Split(key, val) => {
+			let (left, found, right) = tree.split(&key);
+			if found.as_ref() != model.get(&key) {
+				return Err(format!("split found {:?} but model had {:?}", found, model.get(&key)));
+			}
+			let below: BTreeMap<i32,i32> = model.range(..key).map(|(&key, &val)| (key, val)).collect();
+			let above: BTreeMap<i32,i32> = model.range(key + 1..).map(|(&key, &val)| (key, val)).collect();
+			compare(&left, &below).map_err(|message| format!("left of split, {}", message))?;
+			compare(&right, &above).map_err(|message| format!("right of split, {}", message))?;
+			next.insert(key, val);
+			Tree::join(&left, key, val, &right)
+		},
+		Op::Union(index) | Op::Intersection(index) | Op::Difference(index) => {
+			let (ref other, ref other_model) = versions[index % versions.len()];
+			//shared subtrees skip the resolver, so it has to give back a value seen twice
+			let resolver = |_: &i32, ours: &i32, theirs: &i32| *ours.max(theirs);
+			match op {
+				Op::Union(_) => {
+					for (&key, &val) in other_model {
+						let val = next.get(&key).map_or(val, |&ours| ours.max(val));
+						next.insert(key, val);
+					}
+					tree.union(other, resolver)
+				},
+				Op::Intersection(_) => {
+					next = model.iter().filter_map(|(&key, &val)| other_model.get(&key).map(|&theirs| (key, val.max(theirs)))).collect();
+					tree.intersection(other, resolver)
+				},
+				_ => {
+					next.retain(|key, _| !other_model.contains_key(key));
+					tree.difference(other)
+				}
+			}
+		},
+		Op::Range(from, to) => {
+			let (low, high) = (from.min(to), from.max(to));
+			if !tree.range(low..=high).eq(model.range(low..=high)) {
+				return Err(format!("range {:?} but model had {:?}", tree.range(low..=high).collect::<Vec<_>>(), model.range(low..=high).collect::<Vec<_>>()));
+			}
+			if !tree.range(low..=high).rev().eq(model.range(low..=high).rev()) {
+				return Err(format!("reversed range {:?} does not match the model", tree.range(low..=high).rev().collect::<Vec<_>>()));
+			}
+			return Ok(None);
+		},
+		Op::Rank(key) => {
+			let expected = model.range(..key).count();
+			if tree.rank(&key) != expected {
+				return Err(format!("rank {} but model had {}", tree.rank(&key), expected));
+			}
+			return Ok(None);
+		},
+		Op::Select(index) => {
+			if tree.select(index) != model.iter().nth(index) {
+				return Err(format!("selected {:?} but model had {:?}", tree.select(index), model.iter().nth(index)));
+			}
+			return Ok(None);
+		},
+		Op::Floor(key) | Op::Ceiling(key) => {
+			let (found, expected) = match op {
+				Op::Floor(_) => (tree.floor(&key), model.range(..=key).next_back()),
+				_ => (tree.ceiling(&key), model.range(key..).next())
+			};
+			if found != expected {
+				return Err(format!("found {:?} but model had {:?}", found, expected));
+			}
+			return Ok(None);
+		},
+		Op::FromSorted => {
+			Tree::from_sorted_iter(model.iter().map(|(&key, &val)| (key, val))).map_err(|err| err.to_string())?
+		},
+		Op::Transient(key, val) => {
+			let mut transient: TreeTransient<i32,i32> = tree.transient();
+			let (old, expected) = match val {
+				Some(val) => (transient.insert(key, val), next.insert(key, val)),
+				None => (transient.remove(&key), next.remove(&key))
+			};
+			if old != expected {
+				return Err(format!("transient gave back {:?} but model had {:?}", old, expected));
+			}
+			transient.freeze()
+		},
+		Op::Revisit(_) => return Ok(None)
+	};
+	Ok(Some((tree, next)))
+}
+
+///Replays a sequence against both structures, stopping at the first disagreement.
+///The version each step started from is checked again afterwards, so a shared node changed in place shows up too.
+pub fn run(ops: &[Op]) -> Result<(), Failure> {
+	let mut versions = vec![(Tree::new(), BTreeMap::new())];
+	let mut current = 0;
+
+	for (step, &op) in ops.iter().enumerate() {
+		let fail = |message| Failure {step, op, message};
+
+		if let Op::Revisit(index) = op {
+			current = index % versions.len();
+		}
+
+		let next = {
+			let (ref tree, ref model) = versions[current];
+			let next = apply(op, tree, model, &versions).map_err(fail)?;
+			compare(tree, model).map_err(|message| fail(format!("earlier version changed, {}", message)))?;
+			if let Some((ref tree, ref model)) = next {
+				compare(tree, model).map_err(fail)?;
+			}
+			next
+		};
+
+		if let Some(version) = next {
+			versions.push(version);
+			current = versions.len() - 1;
+		}
+	}
+
+	Ok(())
+}
+
+///Shortest sequence found that still fails: drops ever smaller runs of steps, then zeroes the values left.
+///The predicate decides what counts as failing, usually `run(ops).is_err()`.
+pub fn shrink<F>(mut ops: Vec<Op>, mut fails: F) -> Vec<Op> where F: FnMut(&[Op]) -> bool {
+	let mut chunk = ops.len() / 2;
+	while chunk > 0 {
+		let mut start = 0;
+		let mut removed = false;
+		while start < ops.len() {
+			let end = (start + chunk).min(ops.len());
+			let candidate: Vec<Op> = ops[..start].iter().chain(&ops[end..]).cloned().collect();
+			if fails(&candidate) {
+				ops = candidate;
+				removed = true;
+			} else {
+				start += chunk;
+			}
+		}
+		//stay at this size while it keeps paying off
+		if !removed {
+			chunk /= 2;
+		}
+	}
+
+	for index in 0..ops.len() {
+		let simpler = match ops[index] {
+			Op::Insert(key, _) => Op::Insert(key, 0),
+			Op::Replace(key, _) => Op::Replace(key, 0),
+			Op::Update(key, Some(_)) => Op::Update(key, Some(0)),
+			Op::Split(key, _) => Op::Split(key, 0),
+			Op::Transient(key, Some(_)) => Op::Transient(key, Some(0)),
+			Op::Union(_) => Op::Union(0),
+			Op::Intersection(_) => Op::Intersection(0),
+			Op::Difference(_) => Op::Difference(0),
+			Op::Revisit(_) => Op::Revisit(0),
+			op => op
+		};
+		if simpler != ops[index] {
+			let original = ops[index];
+			ops[index] = simpler;
+			if !fails(&ops) {
+				ops[index] = original;
+			}
+		}
+	}
+
+	ops
+}
+
+///Runs a random sequence of the given length, returning a shrunk counterexample if it fails
+pub fn check(seed: u64, steps: usize) -> Result<(), Counterexample> {
+	let keys = (steps as u32 / 4).max(8);
+	let ops = generate(&mut Rng::new(seed), steps, keys);
+
+	if run(&ops).is_ok() {
+		return Ok(());
+	}
+
+	let ops = shrink(ops, |ops| run(ops).is_err());
+	let failure = run(&ops).unwrap_err();
+	Err(Counterexample {seed, ops, failure})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check, generate, run, shrink, Op, Rng};
+
+	#[test]
+	fn test_random_sequences() {
+		for seed in 0..30 {
+			if let Err(counterexample) = check(seed, 400) {
+				panic!("{}", counterexample);
+			}
+		}
+	}
+
+	#[test]
+	fn test_seed_replays() {
+		let first = generate(&mut Rng::new(42), 100, 16);
+		assert_eq!(first, generate(&mut Rng::new(42), 100, 16));
+		assert_ne!(first, generate(&mut Rng::new(43), 100, 16));
+		assert!((0..1000).all(|_| Rng::new(7).below(10) < 10));
+	}
+
+	#[test]
+	fn test_revisit_old_versions() {
+		let ops = [Op::Insert(1, 10), Op::Insert(2, 20), Op::Revisit(1), Op::Remove(1), Op::Revisit(2), Op::Replace(1, 5), Op::PopLast];
+		assert_eq!(run(&ops), Ok(()));
+	}
+
+	#[test]
+	fn test_bulk_operations() {
+		let ops = [
+			Op::Insert(1, 10), Op::Insert(5, 50), Op::Insert(3, 30), Op::Revisit(1), Op::Insert(4, 40),
+			Op::Union(3), Op::Intersection(3), Op::Revisit(5), Op::Difference(2), Op::Split(4, 7),
+			Op::Range(6, 0), Op::Rank(4), Op::Select(2), Op::Floor(2), Op::Ceiling(2),
+			Op::FromSorted, Op::Transient(9, Some(90)), Op::Transient(1, None), Op::Transient(8, None)
+		];
+		assert_eq!(run(&ops), Ok(()));
+	}
+
+	#[test]
+	fn test_shrink() {
+		//stand-in bug: fails whenever key 7 goes in and key 3 comes out later on
+		let fails = |ops: &[Op]| {
+			let inserted = ops.iter().position(|op| matches!(*op, Op::Insert(7, _)));
+			inserted.is_some_and(|index| ops[index..].contains(&Op::Remove(3)))
+		};
+
+		let mut ops = generate(&mut Rng::new(1), 300, 50);
+		ops.insert(40, Op::Insert(7, 123));
+		ops.push(Op::Remove(3));
+		assert!(fails(&ops));
+
+		assert_eq!(shrink(ops, fails), vec![Op::Insert(7, 0), Op::Remove(3)]);
+	}
+}
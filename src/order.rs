@@ -0,0 +1,263 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+use super::{Comparator, HasSize, RemovalResultRecursion, Summary, Tree};
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Number of keys less than the given key, which is its index when present
+	pub fn rank<Q>(&self, search_key: &Q) -> usize where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		let mut current = self.root.as_ref();
+		let mut below = 0;
+
+		while let Some(ref node) = *current {
+			match self.cmp.compare(search_key, node.key().borrow()) {
+				Ordering::Less => current = node.left.as_ref(),
+				Ordering::Greater => {
+					below += node.left.size() + 1;
+					current = node.right.as_ref();
+				},
+				Ordering::Equal => return below + node.left.size()
+			}
+		}
+
+		below
+	}
+
+	///Entry at the given index in key order
+	pub fn select(&self, index: usize) -> Option<(&K, &V)> {
+		let mut current = self.root.as_ref();
+		let mut index = index;
+
+		while let Some(ref node) = *current {
+			let left_size = node.left.size();
+			match index.cmp(&left_size) {
+				Ordering::Less => current = node.left.as_ref(),
+				Ordering::Greater => {
+					index -= left_size + 1;
+					current = node.right.as_ref();
+				},
+				Ordering::Equal => return Some((node.key(), node.val()))
+			}
+		}
+
+		None
+	}
+
+	///Entry with the smallest key
+	pub fn first(&self) -> Option<(&K, &V)> {
+		let mut node = self.root.as_ref().as_ref()?;
+		while let Some(ref left) = *node.left {
+			node = left;
+		}
+		Some((node.key(), node.val()))
+	}
+
+	///Entry with the largest key
+	pub fn last(&self) -> Option<(&K, &V)> {
+		let mut node = self.root.as_ref().as_ref()?;
+		while let Some(ref right) = *node.right {
+			node = right;
+		}
+		Some((node.key(), node.val()))
+	}
+
+	///Version without the smallest entry, along with that entry
+	pub fn pop_first(&self) -> Option<(Self, (K, V))> where K: Clone, V: Clone {
+		if self.root.is_none() {
+			return None;
+		}
+
+		let (result, removed) = self.root.as_ref().as_ref().unwrap().remove_min();
+		match result {
+			RemovalResultRecursion::Standard(root) | RemovalResultRecursion::Shortened(root) => {
+				Some((self.with_root(root), (removed.key().clone(), removed.val().clone())))
+			}
+		}
+	}
+
+	///Version without the largest entry, along with that entry
+	pub fn pop_last(&self) -> Option<(Self, (K, V))> where K: Clone, V: Clone {
+		if self.root.is_none() {
+			return None;
+		}
+
+		let (result, removed) = self.root.as_ref().as_ref().unwrap().remove_max();
+		match result {
+			RemovalResultRecursion::Standard(root) | RemovalResultRecursion::Shortened(root) => {
+				Some((self.with_root(root), (removed.key().clone(), removed.val().clone())))
+			}
+		}
+	}
+
+	///Largest entry with a key no greater than the given key
+	pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		self.closest(key, true, true)
+	}
+
+	///Smallest entry with a key no less than the given key
+	pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		self.closest(key, false, true)
+	}
+
+	///Largest entry with a key strictly less than the given key
+	pub fn predecessor<Q>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		self.closest(key, true, false)
+	}
+
+	///Smallest entry with a key strictly greater than the given key
+	pub fn successor<Q>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		self.closest(key, false, false)
+	}
+
+	///Nearest entry on one side of the key, remembering the last candidate passed on the way down
+	fn closest<Q>(&self, key: &Q, below: bool, inclusive: bool) -> Option<(&K, &V)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		let mut current = self.root.as_ref();
+		let mut best = None;
+
+		while let Some(ref node) = *current {
+			match self.cmp.compare(key, node.key().borrow()) {
+				Ordering::Equal if inclusive => return Some((node.key(), node.val())),
+				Ordering::Greater if below => {
+					best = Some(node);
+					current = node.right.as_ref();
+				},
+				Ordering::Less if !below => {
+					best = Some(node);
+					current = node.left.as_ref();
+				},
+				Ordering::Less | Ordering::Equal if below => current = node.left.as_ref(),
+				_ => current = node.right.as_ref()
+			}
+		}
+
+		best.map(|node| (node.key(), node.val()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::Tree;
+	use super::super::tests::{build, check_tree};
+
+	#[test]
+	fn test_len() {
+		let mut tree = Tree::new();
+		assert_eq!(tree.len(), 0);
+		assert!(tree.is_empty());
+
+		for key in 0..20 {
+			tree = tree.insert(key * 3, ()).unwrap();
+			assert_eq!(tree.len(), key as usize + 1);
+		}
+
+		let (smaller, _) = tree.remove(&9).unwrap();
+		assert_eq!(smaller.len(), 19);
+		assert_eq!(tree.len(), 20);
+		assert!(!smaller.is_empty());
+	}
+
+	#[test]
+	fn test_rank_select() {
+		let tree = build([13, 8, 17, 1, 11, 15, 25, 6, 22, 27]);
+		let sorted: Vec<_> = tree.keys().cloned().collect();
+
+		for (index, key) in sorted.iter().enumerate() {
+			assert_eq!(tree.rank(key), index);
+			assert_eq!(tree.select(index), Some((key, &(key * 10))));
+		}
+
+		assert_eq!(tree.rank(&0), 0);
+		assert_eq!(tree.rank(&12), 4);
+		assert_eq!(tree.rank(&100), 10);
+		assert_eq!(tree.select(10), None);
+	}
+
+	#[test]
+	fn test_iter_exact_size() {
+		let tree = (0..40).fold(Tree::new(), |tree, key| tree.insert(key, ()).unwrap());
+
+		let mut iter = tree.iter();
+		assert_eq!(iter.len(), 40);
+		iter.next();
+		iter.next_back();
+		assert_eq!(iter.len(), 38);
+
+		assert_eq!(tree.range(10..20).len(), 10);
+		assert_eq!(tree.range(..=5).len(), 6);
+		assert_eq!(tree.range(35..100).len(), 5);
+		assert_eq!(tree.range(50..).len(), 0);
+	}
+
+	#[test]
+	fn test_neighbours() {
+		let tree = build([13, 8, 17, 1, 11, 15, 25, 6, 22, 27]);
+
+		assert_eq!(tree.floor(&11), Some((&11, &110)));
+		assert_eq!(tree.floor(&12), Some((&11, &110)));
+		assert_eq!(tree.floor(&0), None);
+		assert_eq!(tree.ceiling(&11), Some((&11, &110)));
+		assert_eq!(tree.ceiling(&12), Some((&13, &130)));
+		assert_eq!(tree.ceiling(&28), None);
+
+		assert_eq!(tree.predecessor(&11), Some((&8, &80)));
+		assert_eq!(tree.predecessor(&12), Some((&11, &110)));
+		assert_eq!(tree.predecessor(&1), None);
+		assert_eq!(tree.successor(&11), Some((&13, &130)));
+		assert_eq!(tree.successor(&26), Some((&27, &270)));
+		assert_eq!(tree.successor(&27), None);
+
+		assert_eq!(Tree::<i32,i32>::new().floor(&5), None);
+	}
+
+	#[test]
+	fn test_neighbours_exhaustive() {
+		let tree = (0..100).fold(Tree::new(), |tree, key| tree.insert(key * 3, ()).unwrap());
+		let keys: Vec<i32> = tree.keys().cloned().collect();
+
+		for key in -2..302 {
+			assert_eq!(tree.floor(&key).map(|(k, _)| *k), keys.iter().cloned().rev().find(|&k| k <= key));
+			assert_eq!(tree.ceiling(&key).map(|(k, _)| *k), keys.iter().cloned().find(|&k| k >= key));
+			assert_eq!(tree.predecessor(&key).map(|(k, _)| *k), keys.iter().cloned().rev().find(|&k| k < key));
+			assert_eq!(tree.successor(&key).map(|(k, _)| *k), keys.iter().cloned().find(|&k| k > key));
+		}
+	}
+
+	#[test]
+	fn test_first_last() {
+		let tree = build([13, 8, 17, 1, 11]);
+		assert_eq!(tree.first(), Some((&1, &10)));
+		assert_eq!(tree.last(), Some((&17, &170)));
+
+		let empty: Tree<i32,i32> = Tree::new();
+		assert_eq!(empty.first(), None);
+		assert_eq!(empty.last(), None);
+		assert!(empty.pop_first().is_none());
+		assert!(empty.pop_last().is_none());
+	}
+
+	#[test]
+	fn test_pop_queue() {
+		let start = (0..300).fold(Tree::new(), |tree, key| tree.insert((key * 7) % 300, key).unwrap());
+
+		let mut tree = start.insert(1000, 0).unwrap();
+		for expected in 0..150 {
+			let (next, (key, _)) = tree.pop_first().unwrap();
+			assert_eq!(key, expected);
+			check_tree(&next);
+			tree = next;
+		}
+		//the popped entry outlives the version it came from
+		let (next, entry) = tree.pop_last().unwrap();
+		tree = next;
+		assert_eq!(entry, (1000, 0));
+		for expected in (150..300).rev() {
+			let (next, (key, _)) = tree.pop_last().unwrap();
+			assert_eq!(key, expected);
+			check_tree(&next);
+			tree = next;
+		}
+
+		assert!(tree.is_empty());
+		assert_eq!(start.len(), 300);
+	}
+}
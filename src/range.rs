@@ -0,0 +1,153 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+use super::{Comparator, HasSize, Summary, Tree, TreeNode};
+use super::iter::Iter;
+
+///In-order iterator over the entries of a tree with keys inside a range
+pub struct Range<'a,K,V,S=()> {
+	inner: Iter<'a,K,V,S>
+}
+
+pub(crate) fn above_lower<Q,C>(cmp: &C, key: &Q, lower: Bound<&Q>) -> bool where Q: ?Sized, C: Comparator<Q> {
+	match lower {
+		Bound::Included(bound) => cmp.compare(key, bound) != Ordering::Less,
+		Bound::Excluded(bound) => cmp.compare(key, bound) == Ordering::Greater,
+		Bound::Unbounded => true
+	}
+}
+
+pub(crate) fn below_upper<Q,C>(cmp: &C, key: &Q, upper: Bound<&Q>) -> bool where Q: ?Sized, C: Comparator<Q> {
+	match upper {
+		Bound::Included(bound) => cmp.compare(key, bound) != Ordering::Greater,
+		Bound::Excluded(bound) => cmp.compare(key, bound) == Ordering::Less,
+		Bound::Unbounded => true
+	}
+}
+
+impl<'a,K,V,S> Iterator for Range<'a,K,V,S> {
+	type Item = (&'a K, &'a V);
+
+	fn next(&mut self) -> Option<(&'a K, &'a V)> {
+		self.inner.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl<'a,K,V,S> DoubleEndedIterator for Range<'a,K,V,S> {
+	fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+		self.inner.next_back()
+	}
+}
+
+impl<'a,K,V,S> ExactSizeIterator for Range<'a,K,V,S> {}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Iterates over the entries with keys inside the range, in key order
+	pub fn range<Q,R>(&self, range: R) -> Range<'_,K,V,S> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, R: RangeBounds<Q> {
+		let lower = range.start_bound();
+		let upper = range.end_bound();
+
+		//path to the first key inside the lower bound
+		let mut front: Vec<&TreeNode<K,V,S>> = Vec::new();
+		let mut below = 0;
+		let mut current = self.root.as_ref();
+		while let Some(ref node) = *current {
+			if above_lower(&self.cmp, node.key().borrow(), lower) {
+				front.push(node);
+				current = node.left.as_ref();
+			} else {
+				//node and its left subtree are all too small
+				below += node.left.size() + 1;
+				current = node.right.as_ref();
+			}
+		}
+
+		//path to the last key inside the upper bound
+		let mut back: Vec<&TreeNode<K,V,S>> = Vec::new();
+		let mut above = 0;
+		let mut current = self.root.as_ref();
+		while let Some(ref node) = *current {
+			if below_upper(&self.cmp, node.key().borrow(), upper) {
+				back.push(node);
+				current = node.right.as_ref();
+			} else {
+				//node and its right subtree are all too large
+				above += node.right.size() + 1;
+				current = node.left.as_ref();
+			}
+		}
+
+		//bounds may cross, leaving nothing in between
+		let remaining = self.len().saturating_sub(below + above);
+
+		Range {inner: Iter::from_stacks(front, back, remaining)}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ops::Bound;
+
+	use super::super::Tree;
+	use super::super::tests::build;
+
+	fn keys<'a, I: Iterator<Item=(&'a i32, &'a i32)>>(iter: I) -> Vec<i32> {
+		iter.map(|(key, _)| *key).collect()
+	}
+
+	#[test]
+	fn test_range_bounds() {
+		let tree = build([13, 8, 17, 1, 11, 15, 25, 6, 22, 27]);
+
+		assert_eq!(keys(tree.range(8..17)), vec![8, 11, 13, 15]);
+		assert_eq!(keys(tree.range(8..=17)), vec![8, 11, 13, 15, 17]);
+		assert_eq!(keys(tree.range(9..16)), vec![11, 13, 15]);
+		assert_eq!(keys(tree.range(..11)), vec![1, 6, 8]);
+		assert_eq!(keys(tree.range(22..)), vec![22, 25, 27]);
+		assert_eq!(keys(tree.range::<i32,_>(..)), vec![1, 6, 8, 11, 13, 15, 17, 22, 25, 27]);
+		assert_eq!(keys(tree.range((Bound::Excluded(8), Bound::Excluded(17)))), vec![11, 13, 15]);
+		assert_eq!(keys(tree.range((Bound::Excluded(8), Bound::Included(11)))), vec![11]);
+	}
+
+	#[test]
+	fn test_range_empty() {
+		let tree = build([13, 8, 17, 1, 11, 15, 25, 6, 22, 27]);
+
+		assert_eq!(keys(tree.range(9..11)), vec![]);
+		assert_eq!(keys(tree.range(28..)), vec![]);
+		assert_eq!(keys(tree.range(..1)), vec![]);
+		assert_eq!(keys(tree.range((Bound::Excluded(13), Bound::Excluded(13)))), vec![]);
+		assert_eq!(keys(Tree::<i32,i32>::new().range(1..5)), vec![]);
+	}
+
+	#[test]
+	fn test_range_double_ended() {
+		let keys_in: Vec<i32> = (0..100).map(|key| key * 2).collect();
+		let tree = build(keys_in.clone());
+
+		let backward: Vec<_> = tree.range(31..=60).rev().map(|(key, _)| *key).collect();
+		let expected: Vec<_> = (16..31).rev().map(|key| key * 2).collect();
+		assert_eq!(backward, expected);
+
+		let mut range = tree.range(10..20);
+		assert_eq!(range.next(), Some((&10, &100)));
+		assert_eq!(range.next_back(), Some((&18, &180)));
+		assert_eq!(keys(range), vec![12, 14, 16]);
+	}
+
+	#[test]
+	fn test_range_borrowed() {
+		let tree = Tree::new()
+			.insert("apple".to_owned(), 1).unwrap()
+			.insert("banana".to_owned(), 2).unwrap()
+			.insert("cherry".to_owned(), 3).unwrap();
+
+		let found: Vec<_> = tree.range::<str,_>((Bound::Included("b"), Bound::Excluded("c"))).map(|(_, val)| *val).collect();
+		assert_eq!(found, vec![2]);
+	}
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+
+use super::{Natural, Ptr, Tree, TreeNode};
+use super::iter::Iter;
+
+///Sideways drawing of a tree, one node per line with the right subtree above and the left below.
+///Each line shows the key and colour, plus the value after `with_values`.
+pub struct Render<'a,K,V,S=(),C=Natural> {
+	tree: &'a Tree<K,V,S,C>,
+	values: bool,
+}
+
+impl<'a,K,V,S,C> Render<'a,K,V,S,C> {
+	///Shows each node's value after its key and colour
+	pub fn with_values(self) -> Render<'a,K,V,S,C> {
+		Render {values: true, ..self}
+	}
+
+	///Writes a subtree below the given prefix, `side` is the connector leading into its root
+	fn write_link(&self, f: &mut fmt::Formatter, current: &Ptr<Option<TreeNode<K,V,S>>>, prefix: &str, side: Side) -> fmt::Result where K: fmt::Debug, V: fmt::Debug {
+		let node = match **current {
+			None => return Ok(()),
+			Some(ref node) => node
+		};
+
+		//lines on the outer side of a child need no rail back up to it
+		let (above, below) = match side {
+			Side::Root => (String::new(), String::new()),
+			Side::Right => (format!("{}    ", prefix), format!("{}|   ", prefix)),
+			Side::Left => (format!("{}|   ", prefix), format!("{}    ", prefix))
+		};
+
+		self.write_link(f, &node.right, &above, Side::Right)?;
+
+		let connector = match side {
+			Side::Root => "",
+			Side::Right => "/-- ",
+			Side::Left => "\\-- "
+		};
+		write!(f, "{}{}{:?} [{}]", prefix, connector, node.key(), if node.is_red { "R" } else { "B" })?;
+		if self.values {
+			write!(f, " = {:?}", node.val())?;
+		}
+		writeln!(f)?;
+
+		self.write_link(f, &node.left, &below, Side::Left)
+	}
+}
+
+///Which child of its parent a subtree is
+#[derive(Clone, Copy)]
+enum Side {
+	Root,
+	Left,
+	Right,
+}
+
+impl<'a,K,V,S,C> fmt::Display for Render<'a,K,V,S,C> where K: fmt::Debug, V: fmt::Debug {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.tree.root.is_none() {
+			return writeln!(f, "(empty)");
+		}
+		self.write_link(f, &self.tree.root, "", Side::Root)
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> {
+	///Compact drawing of the tree's shape for printing, see `Render`
+	pub fn render(&self) -> Render<'_,K,V,S,C> {
+		Render {tree: self, values: false}
+	}
+}
+
+///Entries in key order, like a map
+impl<K,V,S,C> fmt::Debug for Tree<K,V,S,C> where K: fmt::Debug, V: fmt::Debug {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_map().entries(Iter::new(&self.root)).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::Tree;
+	use super::super::tests::build;
+
+	#[test]
+	fn test_render() {
+		let tree = build([13, 8, 17, 1, 11]);
+		assert_eq!(tree.render().to_string(), concat!(
+			"/-- 17 [B]\n",
+			"13 [B]\n",
+			"|   /-- 11 [R]\n",
+			"\\-- 8 [B]\n",
+			"    \\-- 1 [R]\n"
+		));
+
+		let tree = build([2, 1, 3]);
+		assert_eq!(tree.render().with_values().to_string(), "/-- 3 [R] = 30\n2 [B] = 20\n\\-- 1 [R] = 10\n");
+
+		assert_eq!(Tree::<i32,i32>::new().render().to_string(), "(empty)\n");
+	}
+
+	#[test]
+	fn test_render_deep() {
+		let tree = build(0..20);
+		let drawing = tree.render().to_string();
+		assert_eq!(drawing.lines().count(), 20);
+		//right subtree is drawn first, so keys come out in descending order
+		let keys: Vec<i32> = drawing.lines().map(|line| line.trim_start_matches(|c| " |/\\-".contains(c)).split(' ').next().unwrap().parse().unwrap()).collect();
+		assert_eq!(keys, (0..20).rev().collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn test_debug() {
+		let tree = build([13, 8, 17]);
+		assert_eq!(format!("{:?}", tree), "{8: 80, 13: 130, 17: 170}");
+		assert_eq!(format!("{:?}", Tree::<i32,i32>::new()), "{}");
+	}
+}
@@ -0,0 +1,174 @@
+use std::borrow::Borrow;
+use std::fmt;
+
+use super::Tree;
+use super::iter::Keys;
+
+///Persistent ordered set, sharing structure between versions like `Tree`.
+///Keys sit in the same nodes with a zero-sized unit beside them, so each entry costs only its key.
+pub struct TreeSet<K> {
+	tree: Tree<K,()>
+}
+
+///In-order iterator over the keys of a set
+pub struct SetIter<'a,K> {
+	inner: Keys<'a,K,()>
+}
+
+impl<'a,K> Iterator for SetIter<'a,K> {
+	type Item = &'a K;
+
+	fn next(&mut self) -> Option<&'a K> {
+		self.inner.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl<'a,K> DoubleEndedIterator for SetIter<'a,K> {
+	fn next_back(&mut self) -> Option<&'a K> {
+		self.inner.next_back()
+	}
+}
+
+impl<'a,K> ExactSizeIterator for SetIter<'a,K> {}
+
+impl<K> Clone for TreeSet<K> {
+	fn clone(&self) -> TreeSet<K> {
+		TreeSet {tree: self.tree.clone()}
+	}
+}
+
+impl<K> Default for TreeSet<K> where K: Ord {
+	fn default() -> TreeSet<K> {
+		TreeSet::new()
+	}
+}
+
+impl<K> TreeSet<K> where K: Ord {
+	pub fn new() -> TreeSet<K> {
+		TreeSet {tree: Tree::new()}
+	}
+
+	///Number of keys, in constant time
+	pub fn len(&self) -> usize {
+		self.tree.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.tree.is_empty()
+	}
+
+	pub fn contains<Q>(&self, key: &Q) -> bool where K: Borrow<Q>, Q: Ord + ?Sized {
+		self.tree.find(key).is_some()
+	}
+
+	///Version with the key added, or None if it is already present
+	pub fn insert(&self, key: K) -> Option<TreeSet<K>> {
+		self.tree.insert(key, ()).map(|tree| TreeSet {tree})
+	}
+
+	///Version with the key taken out, or None if it is not present
+	pub fn remove<Q>(&self, key: &Q) -> Option<TreeSet<K>> where K: Borrow<Q>, Q: Ord + ?Sized {
+		self.tree.remove(key).map(|(tree, _)| TreeSet {tree})
+	}
+
+	///Iterates over all keys in order
+	pub fn iter(&self) -> SetIter<'_,K> {
+		SetIter {inner: self.tree.keys()}
+	}
+
+	///Keys present in either set
+	pub fn union(&self, other: &TreeSet<K>) -> TreeSet<K> where K: Clone {
+		TreeSet {tree: self.tree.union(&other.tree, |_, _, _| ())}
+	}
+
+	///Keys present in both sets
+	pub fn intersection(&self, other: &TreeSet<K>) -> TreeSet<K> where K: Clone {
+		TreeSet {tree: self.tree.intersection(&other.tree, |_, _, _| ())}
+	}
+
+	///Keys of this set that are not present in the other
+	pub fn difference(&self, other: &TreeSet<K>) -> TreeSet<K> {
+		TreeSet {tree: self.tree.difference(&other.tree)}
+	}
+}
+
+impl<'a,K> IntoIterator for &'a TreeSet<K> where K: Ord {
+	type Item = &'a K;
+	type IntoIter = SetIter<'a,K>;
+
+	fn into_iter(self) -> SetIter<'a,K> {
+		self.iter()
+	}
+}
+
+///Keys in order, like a set
+impl<K> fmt::Debug for TreeSet<K> where K: Ord + fmt::Debug {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_set().entries(self.iter()).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::mem;
+
+	use super::TreeSet;
+	use super::super::Tree;
+
+	fn build<I: Iterator<Item=i32>>(keys: I) -> TreeSet<i32> {
+		keys.fold(TreeSet::new(), |set, key| set.insert(key).unwrap())
+	}
+
+	#[test]
+	fn test_set_basics() {
+		let set = build([13, 8, 17, 1, 11].iter().cloned());
+		assert_eq!(set.len(), 5);
+		assert!(set.contains(&8));
+		assert!(!set.contains(&9));
+		assert!(set.insert(8).is_none());
+		assert!(set.remove(&9).is_none());
+
+		let smaller = set.remove(&8).unwrap();
+		assert!(!smaller.contains(&8));
+		assert!(set.contains(&8));
+		assert_eq!(smaller.iter().cloned().collect::<Vec<_>>(), vec![1, 11, 13, 17]);
+		assert_eq!((&set).into_iter().rev().cloned().collect::<Vec<_>>(), vec![17, 13, 11, 8, 1]);
+		assert!(TreeSet::<i32>::new().is_empty());
+	}
+
+	#[test]
+	fn test_set_ops() {
+		let evens = build((0..50).map(|key| key * 2));
+		let threes = build((0..34).map(|key| key * 3));
+
+		let union = evens.union(&threes);
+		assert_eq!(union.tree.check_invariants(), vec![]);
+		assert!(union.iter().cloned().eq((0..100).filter(|key| key % 2 == 0 || key % 3 == 0)));
+		assert!(evens.intersection(&threes).iter().cloned().eq((0..100).filter(|key| key % 6 == 0)));
+		assert!(evens.difference(&threes).iter().cloned().eq((0..100).filter(|key| key % 2 == 0 && key % 3 != 0)));
+	}
+
+	#[test]
+	fn test_debug() {
+		let set = TreeSet::new().insert("b").unwrap().insert("a").unwrap();
+		assert_eq!(format!("{:?}", set), "{\"a\", \"b\"}");
+		assert_eq!(format!("{:?}", TreeSet::<i32>::new()), "{}");
+	}
+
+	#[test]
+	fn test_no_value_storage() {
+		let set = (0..100).fold(TreeSet::new(), |set, key| set.insert(key as u64).unwrap());
+		let map = (0..100).fold(Tree::new(), |tree, key| tree.insert(key as u64, key as u64).unwrap());
+		let set_stats = Tree::memory_stats(vec![&set.tree]);
+		let map_stats = Tree::memory_stats(vec![&map]);
+
+		//same shape, each entry one value smaller
+		assert_eq!(set_stats.nodes, map_stats.nodes);
+		assert_eq!(set_stats.entries, map_stats.entries);
+		assert_eq!(set_stats.bytes + 100 * mem::size_of::<u64>(), map_stats.bytes);
+	}
+}
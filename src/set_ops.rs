@@ -0,0 +1,219 @@
+use super::{Comparator, HasColour, Ptr, RemovalResultRecursion, Summary, Tree, TreeNode};
+use super::split::{black_height, join_links, split_link, Measured};
+
+///Joins two subtrees with no entry between them
+fn concat<K,V,S>(left: Ptr<Option<TreeNode<K,V,S>>>, left_height: usize, right: Ptr<Option<TreeNode<K,V,S>>>, right_height: usize) -> Measured<K,V,S> where S: Summary<K,V> {
+	if right.is_none() {
+		return (left, left_height);
+	}
+	if left.is_none() {
+		return (right, right_height);
+	}
+
+	let (result, min_node) = right.as_ref().as_ref().unwrap().remove_min();
+	let new_right = match result {
+		RemovalResultRecursion::Standard(link) | RemovalResultRecursion::Shortened(link) => link
+	};
+	let new_right_height = black_height(&new_right);
+	join_links(left, left_height, min_node.entry.clone(), new_right, new_right_height)
+}
+
+///Rebuilds a node from new children and entry, reusing the original if nothing changed
+fn rebuild<K,V,S>(current: &Ptr<Option<TreeNode<K,V,S>>>, height: usize, left: Measured<K,V,S>, entry: Ptr<(K,V)>, right: Measured<K,V,S>) -> Measured<K,V,S> where S: Summary<K,V> {
+	let node = current.as_ref().as_ref().unwrap();
+	if Ptr::ptr_eq(&left.0, &node.left) && Ptr::ptr_eq(&entry, &node.entry) && Ptr::ptr_eq(&right.0, &node.right) {
+		(current.clone(), height)
+	} else {
+		join_links(left.0, left.1, entry, right.0, right.1)
+	}
+}
+
+///Entry for a key present on both sides, only consulting the resolver if the entries differ
+fn resolve<K,V,S,F>(node: &TreeNode<K,V,S>, other: &TreeNode<K,V,S>, resolver: &mut F) -> Ptr<(K,V)> where K: Clone, F: FnMut(&K, &V, &V) -> V, S: Summary<K,V> {
+	if Ptr::ptr_eq(&node.entry, &other.entry) {
+		node.entry.clone()
+	} else {
+		Ptr::new((node.key().clone(), resolver(node.key(), node.val(), other.val())))
+	}
+}
+
+fn union_links<K,V,S,C,F>(cmp: &C, current: &Ptr<Option<TreeNode<K,V,S>>>, height: usize, other: &Ptr<Option<TreeNode<K,V,S>>>, other_height: usize, resolver: &mut F) -> Measured<K,V,S> where K: Clone, C: Comparator<K>, F: FnMut(&K, &V, &V) -> V, S: Summary<K,V> {
+	if other.is_none() || Ptr::ptr_eq(current, other) {
+		return (current.clone(), height);
+	}
+
+	match **current {
+		None => (other.clone(), other_height),
+		Some(ref node) => {
+			let child_height = if node.is_red() { height } else { height - 1 };
+			let split = split_link(cmp, other, other_height, node.key());
+
+			let left = union_links(cmp, &node.left, child_height, &split.left, split.left_height, resolver);
+			let right = union_links(cmp, &node.right, child_height, &split.right, split.right_height, resolver);
+			let entry = match split.found {
+				Some(other_node) => resolve(node, other_node, resolver),
+				None => node.entry.clone()
+			};
+
+			rebuild(current, height, left, entry, right)
+		}
+	}
+}
+
+fn intersection_links<K,V,S,C,F>(cmp: &C, current: &Ptr<Option<TreeNode<K,V,S>>>, height: usize, other: &Ptr<Option<TreeNode<K,V,S>>>, other_height: usize, resolver: &mut F) -> Measured<K,V,S> where K: Clone, C: Comparator<K>, F: FnMut(&K, &V, &V) -> V, S: Summary<K,V> {
+	if Ptr::ptr_eq(current, other) {
+		return (current.clone(), height);
+	}
+	if other.is_none() {
+		return (other.clone(), 0);
+	}
+
+	match **current {
+		None => (current.clone(), 0),
+		Some(ref node) => {
+			let child_height = if node.is_red() { height } else { height - 1 };
+			let split = split_link(cmp, other, other_height, node.key());
+
+			let left = intersection_links(cmp, &node.left, child_height, &split.left, split.left_height, resolver);
+			let right = intersection_links(cmp, &node.right, child_height, &split.right, split.right_height, resolver);
+
+			match split.found {
+				Some(other_node) => {
+					let entry = resolve(node, other_node, resolver);
+					rebuild(current, height, left, entry, right)
+				},
+				None => concat(left.0, left.1, right.0, right.1)
+			}
+		}
+	}
+}
+
+fn difference_links<K,V,S,C>(cmp: &C, current: &Ptr<Option<TreeNode<K,V,S>>>, height: usize, other: &Ptr<Option<TreeNode<K,V,S>>>, other_height: usize) -> Measured<K,V,S> where C: Comparator<K>, S: Summary<K,V> {
+	if Ptr::ptr_eq(current, other) {
+		return (Ptr::new(None), 0);
+	}
+	if other.is_none() {
+		return (current.clone(), height);
+	}
+
+	match **current {
+		None => (current.clone(), 0),
+		Some(ref node) => {
+			let child_height = if node.is_red() { height } else { height - 1 };
+			let split = split_link(cmp, other, other_height, node.key());
+
+			let left = difference_links(cmp, &node.left, child_height, &split.left, split.left_height);
+			let right = difference_links(cmp, &node.right, child_height, &split.right, split.right_height);
+
+			match split.found {
+				Some(_) => concat(left.0, left.1, right.0, right.1),
+				None => rebuild(current, height, left, node.entry.clone(), right)
+			}
+		}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Entries present in either tree, with the resolver picking the value for keys present in both.
+	///Subtrees the two trees share are kept as is without consulting the resolver.
+	///Both trees are taken to order their keys the same way, the result keeps this tree's comparator.
+	pub fn union<F>(&self, other: &Self, mut resolver: F) -> Self where K: Clone, F: FnMut(&K, &V, &V) -> V {
+		let (root, _) = union_links(&self.cmp, &self.root, black_height(&self.root), &other.root, black_height(&other.root), &mut resolver);
+		self.with_root(root)
+	}
+
+	///Entries present in both trees, with the resolver picking their values.
+	///Subtrees the two trees share are kept as is without consulting the resolver.
+	pub fn intersection<F>(&self, other: &Self, mut resolver: F) -> Self where K: Clone, F: FnMut(&K, &V, &V) -> V {
+		let (root, _) = intersection_links(&self.cmp, &self.root, black_height(&self.root), &other.root, black_height(&other.root), &mut resolver);
+		self.with_root(root)
+	}
+
+	///Entries of this tree whose keys are not present in the other
+	pub fn difference(&self, other: &Self) -> Self {
+		let (root, _) = difference_links(&self.cmp, &self.root, black_height(&self.root), &other.root, black_height(&other.root));
+		self.with_root(root)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::{Ptr, Tree};
+	use super::super::tests::{build, check_tree};
+
+	fn keys(tree: &Tree<i32,i32>) -> Vec<i32> {
+		tree.keys().cloned().collect()
+	}
+
+	#[test]
+	fn test_union() {
+		let evens = build((0..50).map(|key| key * 2));
+		let threes = build((0..34).map(|key| key * 3));
+
+		let test = evens.union(&threes, |_, a, b| a + b);
+		check_tree(&test);
+
+		let expected: Vec<_> = (0..100).filter(|key| key % 2 == 0 || key % 3 == 0).collect();
+		assert_eq!(keys(&test), expected);
+		assert_eq!(test.find(&6), Some(&120));
+		assert_eq!(test.find(&4), Some(&40));
+		assert_eq!(test.find(&9), Some(&90));
+
+		assert_eq!(keys(&evens.union(&Tree::new(), |_, a, _| *a)), keys(&evens));
+		assert_eq!(keys(&Tree::new().union(&evens, |_, a, _| *a)), keys(&evens));
+	}
+
+	#[test]
+	fn test_intersection() {
+		let evens = build((0..50).map(|key| key * 2));
+		let threes = build((0..34).map(|key| key * 3));
+
+		let test = evens.intersection(&threes, |_, a, b| a - b);
+		check_tree(&test);
+
+		let expected: Vec<_> = (0..100).filter(|key| key % 6 == 0).collect();
+		assert_eq!(keys(&test), expected);
+		assert!(test.values().all(|&val| val == 0));
+		assert!(evens.intersection(&Tree::new(), |_, a, _| *a).is_empty());
+	}
+
+	#[test]
+	fn test_difference() {
+		let evens = build((0..50).map(|key| key * 2));
+		let threes = build((0..34).map(|key| key * 3));
+
+		let test = evens.difference(&threes);
+		check_tree(&test);
+
+		let expected: Vec<_> = (0..100).filter(|key| key % 2 == 0 && key % 3 != 0).collect();
+		assert_eq!(keys(&test), expected);
+		assert!(evens.difference(&evens).is_empty());
+		assert_eq!(keys(&evens.difference(&Tree::new())), keys(&evens));
+	}
+
+	#[test]
+	fn test_shared_versions() {
+		let base = build(0..1000);
+		let (left, _) = base.insert_or_replace(10, -1);
+		let right = base.insert(2000, 0).unwrap();
+
+		let mut calls = 0;
+		let test = left.union(&right, |_, a, _| { calls += 1; *a });
+		check_tree(&test);
+
+		//only the path to the replaced entry differs between the two
+		assert!(calls <= 1);
+		assert_eq!(test.len(), 1001);
+		assert_eq!(test.find(&10), Some(&-1));
+		assert_eq!(test.find(&2000), Some(&0));
+
+		//identical versions come straight back
+		let same = base.union(&base, |_, _, _| panic!("resolver called on shared entry"));
+		assert!(Ptr::ptr_eq(&same.root, &base.root));
+		let same = base.intersection(&base, |_, _, _| panic!("resolver called on shared entry"));
+		assert!(Ptr::ptr_eq(&same.root, &base.root));
+
+		assert!(left.difference(&base).is_empty());
+		assert_eq!(keys(&right.difference(&base)), vec![2000]);
+	}
+}
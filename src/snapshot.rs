@@ -0,0 +1,507 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::{Comparator, Ptr, Summary, Tree, TreeNode};
+
+const MAGIC: &[u8; 4] = b"PRTS";
+const FORMAT_VERSION: u32 = 1;
+///Magic, format version and checksum
+const FRAMING: usize = 4 + 4 + 8;
+
+const RED: u8 = 1;
+const HAS_LEFT: u8 = 2;
+const HAS_RIGHT: u8 = 4;
+
+///Returned by `Tree::read_snapshot` when the bytes are not a snapshot this version can load
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+	///Does not start with the snapshot magic bytes
+	BadMagic,
+	///Written by a newer or unknown format version
+	UnsupportedVersion(u32),
+	///Contents do not match the stored checksum
+	ChecksumMismatch {stored: u64, computed: u64},
+	///Ends before everything announced has been read
+	UnexpectedEof,
+	///Node or entry reference pointing outside what has been read so far
+	InvalidIndex(u64),
+	///Well-framed but malformed contents, such as bad UTF-8 or broken tree invariants
+	InvalidData(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			SnapshotError::BadMagic => write!(f, "not a tree snapshot"),
+			SnapshotError::UnsupportedVersion(version) => write!(f, "unsupported snapshot format version {}", version),
+			SnapshotError::ChecksumMismatch {stored, computed} => write!(f, "checksum {:016x} does not match contents {:016x}", stored, computed),
+			SnapshotError::UnexpectedEof => write!(f, "snapshot ends unexpectedly"),
+			SnapshotError::InvalidIndex(index) => write!(f, "reference to unknown item {}", index),
+			SnapshotError::InvalidData(reason) => write!(f, "invalid snapshot: {}", reason)
+		}
+	}
+}
+
+impl Error for SnapshotError {}
+
+///Binary encoding of keys and values in a snapshot.
+///`decode` reads from the front of the input and advances it past what it consumed.
+pub trait Codec: Sized {
+	fn encode(&self, out: &mut Vec<u8>);
+	fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError>;
+}
+
+///Splits off the next bytes of the input
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], SnapshotError> {
+	if input.len() < len {
+		return Err(SnapshotError::UnexpectedEof);
+	}
+	let (head, rest) = input.split_at(len);
+	*input = rest;
+	Ok(head)
+}
+
+///LEB128, used for counts and references so small snapshots stay small
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		out.push(value as u8 | 0x80);
+		value >>= 7;
+	}
+	out.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, SnapshotError> {
+	let mut value = 0;
+	let mut shift = 0;
+	loop {
+		let byte = take(input, 1)?[0];
+		if shift == 63 && byte > 1 {
+			return Err(SnapshotError::InvalidData("varint overflows 64 bits"));
+		}
+		value |= u64::from(byte & 0x7f) << shift;
+		if byte & 0x80 == 0 {
+			return Ok(value);
+		}
+		shift += 7;
+	}
+}
+
+///Count of the items that follow
+fn read_len(input: &mut &[u8]) -> Result<usize, SnapshotError> {
+	let value = read_varint(input)?;
+	if value > usize::MAX as u64 {
+		return Err(SnapshotError::InvalidData("count does not fit in memory"));
+	}
+	Ok(value as usize)
+}
+
+///Room for a count read from the input, without trusting it past the bytes left
+fn capacity(count: usize, input: &[u8]) -> usize {
+	count.min(input.len())
+}
+
+///FNV-1a
+fn checksum(bytes: &[u8]) -> u64 {
+	bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+macro_rules! codec_int {
+	($($int:ty),*) => {$(
+		impl Codec for $int {
+			fn encode(&self, out: &mut Vec<u8>) {
+				out.extend_from_slice(&self.to_le_bytes());
+			}
+
+			fn decode(input: &mut &[u8]) -> Result<$int, SnapshotError> {
+				let mut bytes = [0; ::std::mem::size_of::<$int>()];
+				let len = bytes.len();
+				bytes.copy_from_slice(take(input, len)?);
+				Ok(<$int>::from_le_bytes(bytes))
+			}
+		}
+	)*}
+}
+
+codec_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Codec for () {
+	fn encode(&self, _: &mut Vec<u8>) {}
+
+	fn decode(_: &mut &[u8]) -> Result<(), SnapshotError> {
+		Ok(())
+	}
+}
+
+impl Codec for bool {
+	fn encode(&self, out: &mut Vec<u8>) {
+		out.push(*self as u8);
+	}
+
+	fn decode(input: &mut &[u8]) -> Result<bool, SnapshotError> {
+		match take(input, 1)?[0] {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(SnapshotError::InvalidData("bool out of range"))
+		}
+	}
+}
+
+impl Codec for String {
+	fn encode(&self, out: &mut Vec<u8>) {
+		write_varint(out, self.len() as u64);
+		out.extend_from_slice(self.as_bytes());
+	}
+
+	fn decode(input: &mut &[u8]) -> Result<String, SnapshotError> {
+		let len = read_len(input)?;
+		let bytes = take(input, len)?;
+		String::from_utf8(bytes.to_vec()).map_err(|_| SnapshotError::InvalidData("string is not UTF-8"))
+	}
+}
+
+impl<T> Codec for Option<T> where T: Codec {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.is_some().encode(out);
+		if let Some(ref value) = *self {
+			value.encode(out);
+		}
+	}
+
+	fn decode(input: &mut &[u8]) -> Result<Option<T>, SnapshotError> {
+		if bool::decode(input)? {
+			T::decode(input).map(Some)
+		} else {
+			Ok(None)
+		}
+	}
+}
+
+impl<T> Codec for Vec<T> where T: Codec {
+	fn encode(&self, out: &mut Vec<u8>) {
+		write_varint(out, self.len() as u64);
+		for item in self {
+			item.encode(out);
+		}
+	}
+
+	fn decode(input: &mut &[u8]) -> Result<Vec<T>, SnapshotError> {
+		let len = read_len(input)?;
+		(0..len).map(|_| T::decode(input)).collect()
+	}
+}
+
+impl<A,B> Codec for (A,B) where A: Codec, B: Codec {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.0.encode(out);
+		self.1.encode(out);
+	}
+
+	fn decode(input: &mut &[u8]) -> Result<(A,B), SnapshotError> {
+		Ok((A::decode(input)?, B::decode(input)?))
+	}
+}
+
+///Numbers each distinct node and entry in the order they are first needed
+struct Writer<K,V,S> {
+	nodes: HashMap<*const Option<TreeNode<K,V,S>>, u64>,
+	entries: HashMap<*const (K,V), u64>,
+	entry_bytes: Vec<u8>,
+	node_bytes: Vec<u8>,
+}
+
+impl<K,V,S> Writer<K,V,S> where K: Codec, V: Codec {
+	///Writes the subtree children first, returns the node's index, or None for an empty link
+	fn visit(&mut self, current: &Ptr<Option<TreeNode<K,V,S>>>) -> Option<u64> {
+		let node = match **current {
+			None => return None,
+			Some(ref node) => node
+		};
+		if let Some(&index) = self.nodes.get(&Ptr::as_ptr(current)) {
+			return Some(index);
+		}
+
+		let left = self.visit(&node.left);
+		let right = self.visit(&node.right);
+
+		let next_entry = self.entries.len() as u64;
+		let entry = *self.entries.entry(Ptr::as_ptr(&node.entry)).or_insert(next_entry);
+		if entry == next_entry {
+			node.entry.0.encode(&mut self.entry_bytes);
+			node.entry.1.encode(&mut self.entry_bytes);
+		}
+
+		let flags = if node.is_red { RED } else { 0 } | if left.is_some() { HAS_LEFT } else { 0 } | if right.is_some() { HAS_RIGHT } else { 0 };
+		self.node_bytes.push(flags);
+		write_varint(&mut self.node_bytes, entry);
+		for child in left.iter().chain(right.iter()) {
+			write_varint(&mut self.node_bytes, *child);
+		}
+
+		let index = self.nodes.len() as u64;
+		self.nodes.insert(Ptr::as_ptr(current), index);
+		Some(index)
+	}
+}
+
+///What decoding has established about a node, so a node shared between versions is only checked once.
+///Sizes need no record, `TreeNode::new` works them out from the children.
+struct Checked {
+	is_red: bool,
+	///Black nodes on each path from this node down to an empty link
+	black_height: usize,
+	///Entry indices of the smallest and largest keys in the subtree
+	min: usize,
+	max: usize,
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Writes the given versions into one snapshot, storing every node and entry they share only once.
+	///Summaries are left out and recomputed on load.
+	pub fn write_snapshot<'a,I>(versions: I) -> Vec<u8> where I: IntoIterator<Item=&'a Tree<K,V,S,C>>, K: Codec + 'a, V: Codec + 'a, S: 'a, C: 'a {
+		let mut writer = Writer {
+			nodes: HashMap::new(),
+			entries: HashMap::new(),
+			entry_bytes: Vec::new(),
+			node_bytes: Vec::new(),
+		};
+		let roots: Vec<Option<u64>> = versions.into_iter().map(|tree| writer.visit(&tree.root)).collect();
+
+		let mut out = Vec::with_capacity(FRAMING + writer.entry_bytes.len() + writer.node_bytes.len());
+		out.extend_from_slice(MAGIC);
+		FORMAT_VERSION.encode(&mut out);
+		write_varint(&mut out, writer.entries.len() as u64);
+		out.extend_from_slice(&writer.entry_bytes);
+		write_varint(&mut out, writer.nodes.len() as u64);
+		out.extend_from_slice(&writer.node_bytes);
+		write_varint(&mut out, roots.len() as u64);
+		for root in roots {
+			//zero for an empty tree, otherwise one past the node index
+			write_varint(&mut out, root.map_or(0, |index| index + 1));
+		}
+
+		let sum = checksum(&out);
+		sum.encode(&mut out);
+		out
+	}
+
+	///Loads the versions from a snapshot, with nodes shared between them as they were when written.
+	///Each version gets the default comparator, and every node is checked against the tree invariants as it is read.
+	pub fn read_snapshot(bytes: &[u8]) -> Result<Vec<Tree<K,V,S,C>>, SnapshotError> where K: Codec, V: Codec, C: Default {
+		Self::read_snapshot_with(bytes, C::default())
+	}
+
+	///Loads the versions from a snapshot like `read_snapshot`, giving each one a clone of the comparator
+	pub fn read_snapshot_with(bytes: &[u8], cmp: C) -> Result<Vec<Tree<K,V,S,C>>, SnapshotError> where K: Codec, V: Codec {
+		if bytes.len() < FRAMING {
+			return Err(SnapshotError::UnexpectedEof);
+		}
+		if &bytes[..4] != MAGIC {
+			return Err(SnapshotError::BadMagic);
+		}
+		let (contents, mut stored) = bytes.split_at(bytes.len() - 8);
+		let mut input = &contents[4..];
+
+		let version = u32::decode(&mut input)?;
+		if version != FORMAT_VERSION {
+			return Err(SnapshotError::UnsupportedVersion(version));
+		}
+		let stored = u64::decode(&mut stored)?;
+		let computed = checksum(contents);
+		if stored != computed {
+			return Err(SnapshotError::ChecksumMismatch {stored, computed});
+		}
+
+		let entry_count = read_len(&mut input)?;
+		let mut entries = Vec::with_capacity(capacity(entry_count, input));
+		for _ in 0..entry_count {
+			let key = K::decode(&mut input)?;
+			let val = V::decode(&mut input)?;
+			entries.push(Ptr::new((key, val)));
+		}
+
+		//children always come before their parents, so references only ever point back
+		let node_count = read_len(&mut input)?;
+		let mut nodes: Vec<Ptr<Option<TreeNode<K,V,S>>>> = Vec::with_capacity(capacity(node_count, input));
+		let mut checked: Vec<Checked> = Vec::with_capacity(nodes.capacity());
+		let nil = Ptr::new(None);
+		for _ in 0..node_count {
+			let flags = take(&mut input, 1)?[0];
+			if flags & !(RED | HAS_LEFT | HAS_RIGHT) != 0 {
+				return Err(SnapshotError::InvalidData("unknown node flags"));
+			}
+			let entry_index = read_varint(&mut input)?;
+			let entry = entries.get(entry_index as usize).ok_or(SnapshotError::InvalidIndex(entry_index))?;
+			let entry_index = entry_index as usize;
+
+			let mut child = |present: bool| {
+				if !present {
+					return Ok(None);
+				}
+				let index = read_varint(&mut input)?;
+				if index as usize >= nodes.len() {
+					return Err(SnapshotError::InvalidIndex(index));
+				}
+				Ok(Some(index as usize))
+			};
+			let left = child(flags & HAS_LEFT != 0)?;
+			let right = child(flags & HAS_RIGHT != 0)?;
+
+			//the children have been checked already, so only this node's own invariants are left
+			let is_red = flags & RED != 0;
+			if is_red && left.iter().chain(&right).any(|&index| checked[index].is_red) {
+				return Err(SnapshotError::InvalidData("red node with a red child"));
+			}
+			let (left_height, min) = match left {
+				None => (0, entry_index),
+				Some(index) => {
+					if cmp.compare(&entries[checked[index].max].0, &entry.0) != Ordering::Less {
+						return Err(SnapshotError::InvalidData("keys out of order"));
+					}
+					(checked[index].black_height, checked[index].min)
+				}
+			};
+			let (right_height, max) = match right {
+				None => (0, entry_index),
+				Some(index) => {
+					if cmp.compare(&entry.0, &entries[checked[index].min].0) != Ordering::Less {
+						return Err(SnapshotError::InvalidData("keys out of order"));
+					}
+					(checked[index].black_height, checked[index].max)
+				}
+			};
+			if left_height != right_height {
+				return Err(SnapshotError::InvalidData("black heights differ"));
+			}
+
+			let link = |index: Option<usize>| index.map_or_else(|| nil.clone(), |index| nodes[index].clone());
+			let node = TreeNode::new(is_red, entry.clone(), link(left), link(right));
+			checked.push(Checked {is_red, black_height: left_height + if is_red { 0 } else { 1 }, min, max});
+			nodes.push(node);
+		}
+
+		let version_count = read_len(&mut input)?;
+		let mut versions = Vec::with_capacity(capacity(version_count, input));
+		for _ in 0..version_count {
+			let root = match read_varint(&mut input)? {
+				0 => nil.clone(),
+				index => {
+					let index = index as usize - 1;
+					if checked.get(index).ok_or(SnapshotError::InvalidIndex(index as u64))?.is_red {
+						return Err(SnapshotError::InvalidData("red root"));
+					}
+					nodes[index].clone()
+				}
+			};
+			versions.push(Tree {root, cmp: cmp.clone()});
+		}
+
+		if !input.is_empty() {
+			return Err(SnapshotError::InvalidData("trailing bytes"));
+		}
+		Ok(versions)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Codec, SnapshotError};
+	use super::super::{MemoryStats, Natural, Tree};
+	use super::super::tests::{build, nil, node};
+
+	fn entries(tree: &Tree<i32,i32>) -> Vec<(i32, i32)> {
+		tree.iter().map(|(key, val)| (*key, *val)).collect()
+	}
+
+	#[test]
+	fn test_roundtrip_shares_nodes() {
+		let base = build(0..300);
+		let inserted = base.insert(1000, 0).unwrap();
+		let (removed, _) = base.remove(&150).unwrap();
+		let (replaced, _) = inserted.insert_or_replace(7, -7);
+		let versions = vec![&base, &inserted, &removed, &replaced, &base];
+
+		let bytes = Tree::write_snapshot(versions.iter().cloned());
+		let loaded: Vec<Tree<i32,i32>> = Tree::read_snapshot(&bytes).unwrap();
+
+		assert_eq!(loaded.len(), versions.len());
+		for (original, loaded) in versions.iter().zip(&loaded) {
+			assert_eq!(entries(original), entries(loaded));
+		}
+
+		let before: MemoryStats = Tree::memory_stats(versions);
+		let after = Tree::memory_stats(&loaded);
+		assert_eq!((after.nodes, after.shared_nodes, after.entries), (before.nodes, before.shared_nodes, before.entries));
+
+		//a second copy of a version costs only its root reference
+		let small = build(0..50);
+		let single = Tree::write_snapshot(vec![&small]);
+		let doubled = Tree::write_snapshot(vec![&small, &small]);
+		assert_eq!(doubled.len(), single.len() + 1);
+	}
+
+	#[test]
+	fn test_roundtrip_strings() {
+		let tree = ["pear", "apple", "fig", ""].iter().fold(Tree::new(), |tree, key| tree.insert(key.to_string(), vec![key.len() as u8]).unwrap());
+		let empty = Tree::new();
+
+		let bytes = Tree::write_snapshot(vec![&tree, &empty]);
+		let loaded: Vec<Tree<String,Vec<u8>>> = Tree::read_snapshot(&bytes).unwrap();
+		assert!(loaded[0].iter().eq(tree.iter()));
+		assert!(loaded[1].is_empty());
+
+		let none: Vec<Tree<i32,i32>> = Tree::read_snapshot(&Tree::<i32,i32>::write_snapshot(vec![])).unwrap();
+		assert!(none.is_empty());
+	}
+
+	#[test]
+	fn test_corrupt_snapshots() {
+		let bytes = Tree::write_snapshot(vec![&build(0..50)]);
+		let read = |bytes: &[u8]| Tree::<i32,i32>::read_snapshot(bytes).err();
+
+		let mut flipped = bytes.clone();
+		flipped[20] ^= 1;
+		assert!(matches!(read(&flipped), Some(SnapshotError::ChecksumMismatch {..})));
+
+		let mut magic = bytes.clone();
+		magic[0] = b'X';
+		assert_eq!(read(&magic), Some(SnapshotError::BadMagic));
+
+		let mut version = bytes.clone();
+		version[4] = 9;
+		assert_eq!(read(&version), Some(SnapshotError::UnsupportedVersion(9)));
+
+		assert_eq!(read(&bytes[..10]), Some(SnapshotError::UnexpectedEof));
+		assert!(read(&bytes[..bytes.len() - 1]).is_some());
+		assert_eq!(read(&bytes), None);
+	}
+
+	#[test]
+	fn test_broken_trees_rejected() {
+		let read = |root| Tree::<i32,i32>::read_snapshot(&Tree::write_snapshot(vec![&Tree {root, cmp: Natural}])).err();
+
+		assert_eq!(read(node(false, 2, node(true, 1, nil(), nil()), node(true, 3, nil(), nil()))), None);
+		assert_eq!(read(node(false, 2, node(true, 3, nil(), nil()), nil())), Some(SnapshotError::InvalidData("keys out of order")));
+		assert_eq!(read(node(false, 2, nil(), node(true, 2, nil(), nil()))), Some(SnapshotError::InvalidData("keys out of order")));
+		assert_eq!(read(node(false, 2, node(true, 1, node(true, 0, nil(), nil()), nil()), node(false, 3, nil(), nil()))), Some(SnapshotError::InvalidData("red node with a red child")));
+		assert_eq!(read(node(false, 2, node(false, 1, nil(), nil()), nil())), Some(SnapshotError::InvalidData("black heights differ")));
+		assert_eq!(read(node(true, 2, nil(), nil())), Some(SnapshotError::InvalidData("red root")));
+		//a grandchild out of order against the root is caught through the recorded bounds
+		assert_eq!(read(node(false, 5, node(false, 2, nil(), node(true, 7, nil(), nil())), node(false, 8, nil(), nil()))), Some(SnapshotError::InvalidData("keys out of order")));
+	}
+
+	#[test]
+	fn test_codec_values() {
+		let mut out = Vec::new();
+		(-5i64, Some(String::from("é"))).encode(&mut out);
+		(true, vec![1u16, 300]).encode(&mut out);
+
+		let mut input = &out[..];
+		assert_eq!(<(i64, Option<String>)>::decode(&mut input), Ok((-5, Some(String::from("é")))));
+		assert_eq!(<(bool, Vec<u16>)>::decode(&mut input), Ok((true, vec![1, 300])));
+		assert!(input.is_empty());
+		assert_eq!(bool::decode(&mut &[2u8][..]), Err(SnapshotError::InvalidData("bool out of range")));
+		assert_eq!(u32::decode(&mut &[1u8, 2][..]), Err(SnapshotError::UnexpectedEof));
+	}
+}
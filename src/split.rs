@@ -0,0 +1,273 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+use super::{Comparator, HasColour, Ptr, Summary, Tree, TreeNode};
+
+///Subtree along with its black height
+pub(crate) type Measured<K,V,S> = (Ptr<Option<TreeNode<K,V,S>>>, usize);
+
+///Black nodes on every path from the root of a subtree down to a leaf
+pub(crate) fn black_height<K,V,S>(mut current: &Ptr<Option<TreeNode<K,V,S>>>) -> usize {
+	let mut height = 0;
+	while let Some(ref node) = **current {
+		if !node.is_red() {
+			height += 1;
+		}
+		current = &node.left;
+	}
+	height
+}
+
+///Recolours a red root black, adjusting the black height to match
+fn blacken<K,V,S>(root: Ptr<Option<TreeNode<K,V,S>>>, height: usize) -> Measured<K,V,S> where S: Summary<K,V> {
+	if root.is_red() {
+		let old_node = root.as_ref().as_ref().unwrap();
+		(old_node.recolour(false, old_node.left.clone(), old_node.right.clone()), height + 1)
+	} else {
+		(root, height)
+	}
+}
+
+///Joins two subtrees of known black height around an entry that sits between them
+pub(crate) fn join_links<K,V,S>(left: Ptr<Option<TreeNode<K,V,S>>>, left_height: usize, entry: Ptr<(K,V)>, right: Ptr<Option<TreeNode<K,V,S>>>, right_height: usize) -> Measured<K,V,S> where S: Summary<K,V> {
+	let (left, left_height) = blacken(left, left_height);
+	let (right, right_height) = blacken(right, right_height);
+
+	match left_height.cmp(&right_height) {
+		Ordering::Equal => (TreeNode::new(true, entry, left, right), left_height),
+		Ordering::Greater => {
+			let joined = join_right(&left, left_height, entry, right, right_height);
+			let double_red = match *joined {
+				Some(ref node) => node.is_red() && node.right.is_red(),
+				None => false
+			};
+			if double_red {
+				blacken(joined, left_height)
+			} else {
+				(joined, left_height)
+			}
+		},
+		Ordering::Less => {
+			let joined = join_left(left, left_height, entry, &right, right_height);
+			let double_red = match *joined {
+				Some(ref node) => node.is_red() && node.left.is_red(),
+				None => false
+			};
+			if double_red {
+				blacken(joined, right_height)
+			} else {
+				(joined, right_height)
+			}
+		}
+	}
+}
+
+///Descends the right spine of the taller left subtree to hang the right subtree off it
+fn join_right<K,V,S>(current: &Ptr<Option<TreeNode<K,V,S>>>, height: usize, entry: Ptr<(K,V)>, right: Ptr<Option<TreeNode<K,V,S>>>, right_height: usize) -> Ptr<Option<TreeNode<K,V,S>>> where S: Summary<K,V> {
+	if !current.is_red() && height == right_height {
+		return TreeNode::new(true, entry, current.clone(), right);
+	}
+
+	let node = current.as_ref().as_ref().unwrap();
+	let child_height = if node.is_red() { height } else { height - 1 };
+	let new_right = join_right(&node.right, child_height, entry, right, right_height);
+
+	if !node.is_red() {
+		if let Some(ref child) = *new_right {
+			if child.is_red() && child.right.is_red() {
+				//   B (self)
+				//    \
+				//     R
+				//      \
+				//       R
+
+				//rotate left and recolour
+				let old_grandchild = child.right.as_ref().as_ref().unwrap();
+				let new_left = node.recolour(false, node.left.clone(), child.left.clone());
+				let new_right = old_grandchild.recolour(false, old_grandchild.left.clone(), old_grandchild.right.clone());
+				return child.recolour(true, new_left, new_right);
+			}
+		}
+	}
+
+	node.recolour(node.is_red(), node.left.clone(), new_right)
+}
+
+///Descends the left spine of the taller right subtree to hang the left subtree off it
+fn join_left<K,V,S>(left: Ptr<Option<TreeNode<K,V,S>>>, left_height: usize, entry: Ptr<(K,V)>, current: &Ptr<Option<TreeNode<K,V,S>>>, height: usize) -> Ptr<Option<TreeNode<K,V,S>>> where S: Summary<K,V> {
+	if !current.is_red() && height == left_height {
+		return TreeNode::new(true, entry, left, current.clone());
+	}
+
+	let node = current.as_ref().as_ref().unwrap();
+	let child_height = if node.is_red() { height } else { height - 1 };
+	let new_left = join_left(left, left_height, entry, &node.left, child_height);
+
+	if !node.is_red() {
+		if let Some(ref child) = *new_left {
+			if child.is_red() && child.left.is_red() {
+				//       B (self)
+				//      /
+				//     R
+				//    /
+				//   R
+
+				//rotate right and recolour
+				let old_grandchild = child.left.as_ref().as_ref().unwrap();
+				let new_left = old_grandchild.recolour(false, old_grandchild.left.clone(), old_grandchild.right.clone());
+				let new_right = node.recolour(false, child.right.clone(), node.right.clone());
+				return child.recolour(true, new_left, new_right);
+			}
+		}
+	}
+
+	node.recolour(node.is_red(), new_left, node.right.clone())
+}
+
+///Halves of a split subtree with their black heights, and the node holding the key if found
+pub(crate) struct Split<'a,K,V,S> {
+	pub(crate) left: Ptr<Option<TreeNode<K,V,S>>>,
+	pub(crate) left_height: usize,
+	pub(crate) found: Option<&'a TreeNode<K,V,S>>,
+	pub(crate) right: Ptr<Option<TreeNode<K,V,S>>>,
+	pub(crate) right_height: usize,
+}
+
+pub(crate) fn split_link<'a,K,V,S,Q,C>(cmp: &C, current: &'a Ptr<Option<TreeNode<K,V,S>>>, height: usize, key: &Q) -> Split<'a,K,V,S> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, S: Summary<K,V> {
+	match **current {
+		None => Split {left: current.clone(), left_height: 0, found: None, right: current.clone(), right_height: 0},
+		Some(ref node) => {
+			let child_height = if node.is_red() { height } else { height - 1 };
+
+			match cmp.compare(key, node.key().borrow()) {
+				Ordering::Less => {
+					let split = split_link(cmp, &node.left, child_height, key);
+					let (right, right_height) = join_links(split.right, split.right_height, node.entry.clone(), node.right.clone(), child_height);
+					Split {right, right_height, ..split}
+				},
+				Ordering::Greater => {
+					let split = split_link(cmp, &node.right, child_height, key);
+					let (left, left_height) = join_links(node.left.clone(), child_height, node.entry.clone(), split.left, split.left_height);
+					Split {left, left_height, ..split}
+				},
+				Ordering::Equal => Split {
+					left: node.left.clone(),
+					left_height: child_height,
+					found: Some(node),
+					right: node.right.clone(),
+					right_height: child_height
+				}
+			}
+		}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Splits into the entries below the key and the entries above it, along with the key's value if present
+	pub fn split<Q>(&self, key: &Q) -> (Self, Option<V>, Self) where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, V: Clone {
+		let split = split_link(&self.cmp, &self.root, black_height(&self.root), key);
+		let found = split.found.map(|node| node.val().clone());
+		(self.with_root(split.left), found, self.with_root(split.right))
+	}
+
+	///Joins two trees around a new entry, which must sort after every key in left and before every key in right.
+	///The result keeps the comparator of the left tree.
+	pub fn join(left: &Self, key: K, val: V, right: &Self) -> Self {
+		if let Some((max, _)) = left.iter().next_back() {
+			assert!(left.cmp.compare(max, &key) == Ordering::Less, "Left tree has keys above the joining key");
+		}
+		if let Some((min, _)) = right.iter().next() {
+			assert!(left.cmp.compare(min, &key) == Ordering::Greater, "Right tree has keys below the joining key");
+		}
+
+		let (root, _) = join_links(left.root.clone(), black_height(&left.root), Ptr::new((key, val)), right.root.clone(), black_height(&right.root));
+		left.with_root(root)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::{Ptr, Tree};
+	use super::super::tests::{build, check_tree};
+
+	#[test]
+	fn test_split_present() {
+		let tree = build(0..100);
+		let (left, found, right) = tree.split(&40);
+
+		assert_eq!(found, Some(400));
+		check_tree(&left);
+		check_tree(&right);
+		assert_eq!(left.keys().cloned().collect::<Vec<_>>(), (0..40).collect::<Vec<_>>());
+		assert_eq!(right.keys().cloned().collect::<Vec<_>>(), (41..100).collect::<Vec<_>>());
+		assert_eq!(tree.len(), 100);
+	}
+
+	#[test]
+	fn test_split_absent() {
+		let tree = build((0..50).map(|key| key * 2));
+
+		for key in -1..101 {
+			let (left, found, right) = tree.split(&key);
+			check_tree(&left);
+			check_tree(&right);
+			assert_eq!(found.is_some(), key % 2 == 0 && (0..100).contains(&key));
+			assert!(left.keys().all(|&k| k < key));
+			assert!(right.keys().all(|&k| k > key));
+			assert_eq!(left.len() + right.len() + found.iter().count(), 50);
+		}
+	}
+
+	#[test]
+	fn test_join_uneven() {
+		let small = build(0..3);
+		let large = build(10..500);
+
+		let joined = Tree::join(&small, 5, 50, &large);
+		check_tree(&joined);
+		assert_eq!(joined.len(), 494);
+		assert_eq!(joined.find(&5), Some(&50));
+
+		let joined = Tree::join(&large, 1000, 0, &build(2000..2003));
+		check_tree(&joined);
+		assert_eq!(joined.rank(&1000), 490);
+
+		let joined = Tree::join(&Tree::new(), 5, 50, &Tree::new());
+		check_tree(&joined);
+		assert_eq!(joined.len(), 1);
+	}
+
+	#[test]
+	fn test_join_shares_subtrees() {
+		let small = build(0..3);
+		let large = build(10..500);
+		let joined = Tree::join(&small, 5, 50, &large);
+
+		//the smaller tree hangs off the left spine unchanged
+		let mut current = &joined.root;
+		let mut shared = false;
+		while let Some(ref node) = **current {
+			shared |= Ptr::ptr_eq(&node.left, &small.root);
+			current = &node.left;
+		}
+		assert!(shared);
+	}
+
+	#[test]
+	fn test_split_join_roundtrip() {
+		let tree = build((0..300).map(|key| (key * 37) % 300));
+
+		for key in (0..300).step_by(7) {
+			let (left, found, right) = tree.split(&key);
+			let joined = Tree::join(&left, key, found.unwrap(), &right);
+			check_tree(&joined);
+			assert!(joined.iter().eq(tree.iter()));
+		}
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_join_misordered() {
+		Tree::join(&build(0..10), 5, 50, &Tree::new());
+	}
+}
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use super::{Comparator, Ptr, Summary, Tree, TreeNode};
+
+///Memory held by a set of tree versions, counting every shared allocation once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryStats {
+	///Number of versions measured
+	pub versions: usize,
+	///Distinct nodes across all versions
+	pub nodes: usize,
+	///Distinct nodes reachable from more than one version
+	pub shared_nodes: usize,
+	///Distinct key-value allocations, which path copies share between nodes
+	pub entries: usize,
+	///Approximate bytes held, leaving out heap memory owned by the keys and values themselves
+	pub bytes: usize,
+}
+
+///Pointer identity of a link, empty links are allocations of their own
+type LinkId<K,V,S> = *const Option<TreeNode<K,V,S>>;
+
+///Version a link was first reached from, and whether another version reaches it too
+struct Seen {
+	version: usize,
+	shared: bool,
+}
+
+struct Accounting<K,V,S> {
+	links: HashMap<LinkId<K,V,S>, Seen>,
+	entries: HashSet<*const (K,V)>,
+	stats: MemoryStats,
+}
+
+impl<K,V,S> Accounting<K,V,S> {
+	fn visit(&mut self, current: &Ptr<Option<TreeNode<K,V,S>>>, version: usize) {
+		let id = Ptr::as_ptr(current);
+
+		if let Some(seen) = self.links.get_mut(&id) {
+			if seen.shared || seen.version == version {
+				//everything below has been accounted for already
+				return;
+			}
+			//first time another version reaches it, so the whole subtree is shared
+			seen.shared = true;
+			if let Some(ref node) = **current {
+				self.stats.shared_nodes += 1;
+				self.visit(&node.left, version);
+				self.visit(&node.right, version);
+			}
+			return;
+		}
+
+		self.links.insert(id, Seen {version, shared: false});
+		//reference counts sit in the same allocation as the contents
+		self.stats.bytes += mem::size_of::<Option<TreeNode<K,V,S>>>() + 2 * mem::size_of::<usize>();
+
+		if let Some(ref node) = **current {
+			self.stats.nodes += 1;
+			if self.entries.insert(Ptr::as_ptr(&node.entry)) {
+				self.stats.entries += 1;
+				self.stats.bytes += mem::size_of::<(K,V)>() + 2 * mem::size_of::<usize>();
+			}
+			self.visit(&node.left, version);
+			self.visit(&node.right, version);
+		}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Memory held by the given versions together, deduplicating shared nodes by pointer.
+	///The cost is linear in the number of distinct nodes.
+	pub fn memory_stats<'a,I>(versions: I) -> MemoryStats where I: IntoIterator<Item=&'a Tree<K,V,S,C>>, K: 'a, V: 'a, S: 'a, C: 'a {
+		let mut accounting = Accounting {
+			links: HashMap::new(),
+			entries: HashSet::new(),
+			stats: MemoryStats::default(),
+		};
+
+		for (version, tree) in versions.into_iter().enumerate() {
+			accounting.visit(&tree.root, version);
+			accounting.stats.versions += 1;
+		}
+
+		accounting.stats
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+
+	use super::super::{Ptr, Tree, TreeNode};
+	use super::super::tests::build;
+
+	fn node_ids(current: &Ptr<Option<TreeNode<i32,i32>>>, ids: &mut HashSet<*const Option<TreeNode<i32,i32>>>) {
+		if let Some(ref node) = **current {
+			ids.insert(Ptr::as_ptr(current));
+			node_ids(&node.left, ids);
+			node_ids(&node.right, ids);
+		}
+	}
+
+	#[test]
+	fn test_single_version() {
+		let tree = build(0..100);
+		let stats = Tree::memory_stats(vec![&tree]);
+
+		assert_eq!(stats.versions, 1);
+		assert_eq!(stats.nodes, 100);
+		assert_eq!(stats.shared_nodes, 0);
+		assert_eq!(stats.entries, 100);
+		assert!(stats.bytes > 100 * 2 * ::std::mem::size_of::<i32>());
+
+		assert_eq!(Tree::<i32,i32>::memory_stats(vec![]), Default::default());
+	}
+
+	#[test]
+	fn test_shared_versions() {
+		let base = build(0..500);
+		let inserted = base.insert(1000, 0).unwrap();
+		let (removed, _) = base.remove(&250).unwrap();
+		let (replaced, _) = inserted.insert_or_replace(10, -1);
+		let versions = vec![&base, &inserted, &removed, &replaced];
+
+		//reference counts by brute force over every version's node set
+		let sets: Vec<_> = versions.iter().map(|tree| {
+			let mut ids = HashSet::new();
+			node_ids(&tree.root, &mut ids);
+			ids
+		}).collect();
+		let all: HashSet<_> = sets.iter().flat_map(|ids| ids.iter().cloned()).collect();
+		let shared = all.iter().filter(|id| sets.iter().filter(|ids| ids.contains(id)).count() > 1).count();
+
+		let stats = Tree::memory_stats(versions);
+		assert_eq!(stats.versions, 4);
+		assert_eq!(stats.nodes, all.len());
+		assert_eq!(stats.shared_nodes, shared);
+		//only the replaced value and the new key need entries of their own
+		assert_eq!(stats.entries, 502);
+		assert!(stats.nodes < 600);
+
+		//a version measured twice is shared in full
+		let stats = Tree::memory_stats(vec![&base, &base]);
+		assert_eq!(stats.nodes, 500);
+		assert_eq!(stats.shared_nodes, 500);
+	}
+}
@@ -0,0 +1,216 @@
+use std::borrow::Borrow;
+use std::ops::{Bound, RangeBounds};
+
+use super::{Comparator, Ptr, Tree, TreeNode};
+use super::range::{above_lower, below_upper};
+
+///Aggregate over a run of entries, kept in every node so range folds need not visit each entry.
+///`combine` must be associative, with `identity` as its neutral element on both sides.
+pub trait Summary<K,V>: Clone {
+	///Summary of no entries at all
+	fn identity() -> Self;
+	///Summary of a single entry
+	fn of_entry(key: &K, val: &V) -> Self;
+	///Summary of the entries in self followed by the entries in other
+	fn combine(&self, other: &Self) -> Self;
+}
+
+///No summary, the default for trees that do not need one
+impl<K,V> Summary<K,V> for () {
+	fn identity() {}
+
+	fn of_entry(_: &K, _: &V) {}
+
+	fn combine(&self, _: &()) {}
+}
+
+///Summary of a node built from the given children and entry
+pub(crate) fn summarise<K,V,S>(left: &Ptr<Option<TreeNode<K,V,S>>>, entry: &(K,V), right: &Ptr<Option<TreeNode<K,V,S>>>) -> S where S: Summary<K,V> {
+	let own = S::of_entry(&entry.0, &entry.1);
+	let own = match **left {
+		None => own,
+		Some(ref node) => node.summary.combine(&own)
+	};
+	match **right {
+		None => own,
+		Some(ref node) => own.combine(&node.summary)
+	}
+}
+
+fn link_summary<K,V,S>(current: &Ptr<Option<TreeNode<K,V,S>>>) -> S where S: Summary<K,V> {
+	match **current {
+		None => S::identity(),
+		Some(ref node) => node.summary.clone()
+	}
+}
+
+///Summary of the entries in a subtree inside the lower bound
+fn fold_above<K,V,S,Q,C>(cmp: &C, current: &Ptr<Option<TreeNode<K,V,S>>>, lower: Bound<&Q>) -> S where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, S: Summary<K,V> {
+	match **current {
+		None => S::identity(),
+		Some(ref node) => {
+			if above_lower(cmp, node.key().borrow(), lower) {
+				//node and its right subtree are all inside
+				let own = S::of_entry(node.key(), node.val());
+				fold_above(cmp, &node.left, lower).combine(&own).combine(&link_summary(&node.right))
+			} else {
+				fold_above(cmp, &node.right, lower)
+			}
+		}
+	}
+}
+
+///Summary of the entries in a subtree inside the upper bound
+fn fold_below<K,V,S,Q,C>(cmp: &C, current: &Ptr<Option<TreeNode<K,V,S>>>, upper: Bound<&Q>) -> S where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, S: Summary<K,V> {
+	match **current {
+		None => S::identity(),
+		Some(ref node) => {
+			if below_upper(cmp, node.key().borrow(), upper) {
+				//node and its left subtree are all inside
+				let own = S::of_entry(node.key(), node.val());
+				link_summary(&node.left).combine(&own).combine(&fold_below(cmp, &node.right, upper))
+			} else {
+				fold_below(cmp, &node.left, upper)
+			}
+		}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Summary of every entry, in constant time
+	pub fn summary(&self) -> S {
+		link_summary(&self.root)
+	}
+
+	///Summary of the entries with keys inside the range, in logarithmic time
+	pub fn fold_range<Q,R>(&self, range: R) -> S where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, R: RangeBounds<Q> {
+		let lower = range.start_bound();
+		let upper = range.end_bound();
+
+		//descend to the first node inside both bounds, where the two bounds part ways
+		let mut current = &self.root;
+		while let Some(ref node) = **current {
+			if !above_lower(&self.cmp, node.key().borrow(), lower) {
+				current = &node.right;
+			} else if !below_upper(&self.cmp, node.key().borrow(), upper) {
+				current = &node.left;
+			} else {
+				let own = S::of_entry(node.key(), node.val());
+				return fold_above(&self.cmp, &node.left, lower).combine(&own).combine(&fold_below(&self.cmp, &node.right, upper));
+			}
+		}
+
+		S::identity()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ops::Bound;
+
+	use super::Summary;
+	use super::super::{Natural, Reverse, Tree};
+
+	#[derive(Clone, Debug, PartialEq)]
+	struct Stats {
+		count: usize,
+		sum: i64,
+		max: Option<i32>,
+	}
+
+	impl Summary<i32,i32> for Stats {
+		fn identity() -> Stats {
+			Stats {count: 0, sum: 0, max: None}
+		}
+
+		fn of_entry(_: &i32, val: &i32) -> Stats {
+			Stats {count: 1, sum: *val as i64, max: Some(*val)}
+		}
+
+		fn combine(&self, other: &Stats) -> Stats {
+			Stats {count: self.count + other.count, sum: self.sum + other.sum, max: self.max.max(other.max)}
+		}
+	}
+
+	///Keys in order, so only an order-respecting combine gets this right
+	#[derive(Clone, Debug, PartialEq)]
+	struct Concat(Vec<i32>);
+
+	impl Summary<i32,i32> for Concat {
+		fn identity() -> Concat {
+			Concat(Vec::new())
+		}
+
+		fn of_entry(key: &i32, _: &i32) -> Concat {
+			Concat(vec![*key])
+		}
+
+		fn combine(&self, other: &Concat) -> Concat {
+			Concat(self.0.iter().chain(other.0.iter()).cloned().collect())
+		}
+	}
+
+	fn expected<'a, I: Iterator<Item=(&'a i32, &'a i32)>>(entries: I) -> Stats {
+		entries.fold(Stats::identity(), |acc, (key, val)| acc.combine(&Stats::of_entry(key, val)))
+	}
+
+	#[test]
+	fn test_fold_range() {
+		let tree: Tree<i32,i32,Stats> = (0..100).fold(Tree::default(), |tree, key| tree.insert(key * 2, key).unwrap());
+		assert_eq!(tree.summary(), Stats {count: 100, sum: 4950, max: Some(99)});
+
+		for low in -1..202 {
+			for high in (low..202).step_by(5) {
+				assert_eq!(tree.fold_range(low..high), expected(tree.range(low..high)));
+				assert_eq!(tree.fold_range(low..=high), expected(tree.range(low..=high)));
+			}
+			assert_eq!(tree.fold_range(low..), expected(tree.range(low..)));
+			assert_eq!(tree.fold_range(..low), expected(tree.range(..low)));
+		}
+
+		assert_eq!(tree.fold_range((Bound::Excluded(10), Bound::Excluded(12))), Stats::identity());
+		assert_eq!(tree.fold_range((Bound::Included(50), Bound::Excluded(10))), Stats::identity());
+		assert_eq!(Tree::<i32,i32,Stats>::default().summary(), Stats::identity());
+	}
+
+	#[test]
+	fn test_summary_order() {
+		let tree: Tree<i32,i32,Concat> = [13, 8, 17, 1, 11, 15, 25, 6, 22, 27].iter()
+			.fold(Tree::default(), |tree, &key| tree.insert(key, 0).unwrap());
+
+		assert_eq!(tree.summary().0, vec![1, 6, 8, 11, 13, 15, 17, 22, 25, 27]);
+		assert_eq!(tree.fold_range(7..23).0, vec![8, 11, 13, 15, 17, 22]);
+	}
+
+	#[test]
+	fn test_summary_with_comparator() {
+		let tree: Tree<i32,i32,Concat,_> = (0..10).fold(Tree::with_comparator_and_summary(Reverse(Natural)), |tree, key| tree.insert(key, 0).unwrap());
+		assert_eq!(tree.summary().0, (0..10).rev().collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn test_summary_after_modification() {
+		let base: Tree<i32,i32,Stats> = Tree::from_sorted_iter((0..300).map(|key| (key, key % 17))).unwrap();
+		assert_eq!(base.summary(), expected(base.iter()));
+
+		let mut tree = base.insert(300, 5).unwrap();
+		for key in (0..300).step_by(3) {
+			tree = tree.remove(&key).unwrap().0;
+			tree = tree.insert_or_replace(key + 1, 100).0;
+		}
+		assert_eq!(tree.summary(), expected(tree.iter()));
+		assert_eq!(tree.fold_range(100..200), expected(tree.range(100..200)));
+
+		let (left, _, right) = tree.split(&150);
+		assert_eq!(left.summary(), expected(left.iter()));
+		assert_eq!(right.summary(), expected(right.iter()));
+
+		let merged = base.union(&tree, |_, a, b| a + b);
+		assert_eq!(merged.summary(), expected(merged.iter()));
+		let common = base.intersection(&tree, |_, a, b| a - b);
+		assert_eq!(common.fold_range::<i32,_>(..), expected(common.iter()));
+
+		//older versions keep their own summaries
+		assert_eq!(base.summary(), expected(base.iter()));
+	}
+}
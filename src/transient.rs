@@ -0,0 +1,497 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::mem;
+
+use super::{Comparator, HasColour, HasSize, Natural, Ptr, Summary, Tree, TreeNode};
+use super::summary::summarise;
+
+///Shallow copy sharing the entry and both children, made when a transient writes to a shared node
+impl<K,V,S> Clone for TreeNode<K,V,S> where S: Clone {
+	fn clone(&self) -> TreeNode<K,V,S> {
+		TreeNode {
+			is_red: self.is_red,
+			size: self.size,
+			summary: self.summary.clone(),
+			entry: self.entry.clone(),
+			left: self.left.clone(),
+			right: self.right.clone()
+		}
+	}
+}
+
+///Mutable tree for batches of changes, turned back into a `Tree` with `freeze`.
+///Nodes only this transient holds are changed in place, shared ones are copied on first write.
+pub struct TreeTransient<K,V,S=(),C=Natural> {
+	root: Ptr<Option<TreeNode<K,V,S>>>,
+	///Single empty link shared by every leaf the transient creates
+	nil: Ptr<Option<TreeNode<K,V,S>>>,
+	cmp: C,
+}
+
+enum InsertionResult<K,V> {
+	///No additional steps necessary
+	Standard,
+	///Key already present, hands back the entry it held
+	Replaced(Ptr<(K,V)>),
+	///Root of the subtree is red, as is its left child
+	DoubleRedLeft,
+	///Root of the subtree is red, as is its right child
+	DoubleRedRight,
+}
+
+///Node behind a link, copied first unless the link is its only owner
+fn node_mut<K,V,S>(link: &mut Ptr<Option<TreeNode<K,V,S>>>) -> &mut TreeNode<K,V,S> where S: Clone {
+	Ptr::make_mut(link).as_mut().unwrap()
+}
+
+///Recomputes size and summary after the children or entry have changed
+fn refresh<K,V,S>(node: &mut TreeNode<K,V,S>) where S: Summary<K,V> {
+	node.size = node.left.size() + node.right.size() + 1;
+	node.summary = summarise(&node.left, &node.entry, &node.right);
+}
+
+///Sets the colour of a non-empty link, leaving it alone if it already matches
+fn set_colour<K,V,S>(link: &mut Ptr<Option<TreeNode<K,V,S>>>, is_red: bool) where S: Clone {
+	if link.is_red() != is_red {
+		node_mut(link).is_red = is_red;
+	}
+}
+
+fn rotate_left<K,V,S>(link: &mut Ptr<Option<TreeNode<K,V,S>>>, nil: &Ptr<Option<TreeNode<K,V,S>>>) where S: Summary<K,V> {
+	//     A              B
+	//    / \            / \
+	//       B    =>    A
+	//      / \          \
+	//     C               C
+	let mut right = mem::replace(&mut node_mut(link).right, nil.clone());
+	{
+		let node = node_mut(link);
+		mem::swap(&mut node.right, &mut node_mut(&mut right).left);
+		refresh(node);
+	}
+	mem::swap(link, &mut right);
+	let node = node_mut(link);
+	node.left = right;
+	refresh(node);
+}
+
+fn rotate_right<K,V,S>(link: &mut Ptr<Option<TreeNode<K,V,S>>>, nil: &Ptr<Option<TreeNode<K,V,S>>>) where S: Summary<K,V> {
+	//       A          B
+	//      / \        / \
+	//     B    =>        A
+	//    / \            /
+	//       C          C
+	let mut left = mem::replace(&mut node_mut(link).left, nil.clone());
+	{
+		let node = node_mut(link);
+		mem::swap(&mut node.left, &mut node_mut(&mut left).right);
+		refresh(node);
+	}
+	mem::swap(link, &mut left);
+	let node = node_mut(link);
+	node.right = left;
+	refresh(node);
+}
+
+///Hands back the value of an entry taken out of the tree, cloning only if another version still holds it
+fn into_val<K,V>(entry: Ptr<(K,V)>) -> V where V: Clone {
+	match Ptr::try_unwrap(entry) {
+		Ok((_, val)) => val,
+		Err(entry) => entry.1.clone()
+	}
+}
+
+impl<K,V,C> TreeTransient<K,V,(),C> where C: Comparator<K> {
+	///Empty transient ordering its keys by the given comparator
+	pub fn with_comparator(cmp: C) -> TreeTransient<K,V,(),C> {
+		let nil = Ptr::new(None);
+		TreeTransient {root: nil.clone(), nil, cmp}
+	}
+}
+
+impl<K,V,S,C> TreeTransient<K,V,S,C> where C: Comparator<K>, S: Summary<K,V> {
+	///Empty transient
+	pub fn new() -> TreeTransient<K,V,S,C> where C: Default {
+		let nil = Ptr::new(None);
+		TreeTransient {root: nil.clone(), nil, cmp: C::default()}
+	}
+
+	///Number of entries, in constant time
+	pub fn len(&self) -> usize {
+		self.root.size()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.root.is_none()
+	}
+
+	pub fn find<Q>(&self, search_key: &Q) -> Option<&V> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		let mut current = self.root.as_ref();
+
+		while let Some(ref node) = *current {
+			match self.cmp.compare(search_key, node.key().borrow()) {
+				Ordering::Less => current = node.left.as_ref(),
+				Ordering::Greater => current = node.right.as_ref(),
+				Ordering::Equal => return Some(node.val())
+			}
+		}
+
+		None
+	}
+
+	///Inserts the key, or replaces its value if already present, returning the old value
+	pub fn insert(&mut self, key: K, val: V) -> Option<V> where V: Clone {
+		let result = Self::insert_link(&self.cmp, &mut self.root, key, val, &self.nil);
+		set_colour(&mut self.root, false);
+
+		match result {
+			InsertionResult::Replaced(entry) => Some(into_val(entry)),
+			_ => None
+		}
+	}
+
+	///Removes the key, returning its value if it was present
+	pub fn remove<Q>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q>, V: Clone {
+		//looking first means a missing key never copies the nodes on its path
+		self.find(key)?;
+		let removed = Self::remove_link(&self.cmp, &mut self.root, key, &self.nil);
+		if self.root.is_some() {
+			set_colour(&mut self.root, false);
+		}
+
+		removed.map(|(entry, _)| into_val(entry))
+	}
+
+	///Persistent tree holding the current entries
+	pub fn freeze(self) -> Tree<K,V,S,C> {
+		Tree {root: self.root, cmp: self.cmp}
+	}
+
+	fn insert_link(cmp: &C, link: &mut Ptr<Option<TreeNode<K,V,S>>>, key: K, val: V, nil: &Ptr<Option<TreeNode<K,V,S>>>) -> InsertionResult<K,V> {
+		if link.is_none() {
+			*link = TreeNode::new(true, Ptr::new((key, val)), nil.clone(), nil.clone());
+			return InsertionResult::Standard;
+		}
+
+		let node = node_mut(link);
+		let (result, left) = match cmp.compare(&key, node.key()) {
+			Ordering::Equal => {
+				let old = mem::replace(&mut node.entry, Ptr::new((key, val)));
+				refresh(node);
+				return InsertionResult::Replaced(old);
+			},
+			Ordering::Less => (Self::insert_link(cmp, &mut node.left, key, val, nil), true),
+			Ordering::Greater => (Self::insert_link(cmp, &mut node.right, key, val, nil), false)
+		};
+		refresh(node);
+
+		match result {
+			InsertionResult::Replaced(old) => InsertionResult::Replaced(old),
+			InsertionResult::Standard => {
+				let child_red = if left { node.left.is_red() } else { node.right.is_red() };
+				if !node.is_red || !child_red {
+					InsertionResult::Standard
+				} else if left {
+					InsertionResult::DoubleRedLeft
+				} else {
+					InsertionResult::DoubleRedRight
+				}
+			},
+			InsertionResult::DoubleRedLeft | InsertionResult::DoubleRedRight => {
+				assert!(!node.is_red);
+				let sibling_red = if left { node.right.is_red() } else { node.left.is_red() };
+
+				if sibling_red {
+					//recolour self and both children
+					set_colour(&mut node.left, false);
+					set_colour(&mut node.right, false);
+					node.is_red = true;
+					return InsertionResult::Standard;
+				}
+
+				//bring the red grandchild to the outside, then rotate it up
+				match (left, result) {
+					(true, InsertionResult::DoubleRedRight) => rotate_left(&mut node.left, nil),
+					(false, InsertionResult::DoubleRedLeft) => rotate_right(&mut node.right, nil),
+					_ => {}
+				}
+				if left {
+					rotate_right(link, nil);
+				} else {
+					rotate_left(link, nil);
+				}
+
+				let top = node_mut(link);
+				top.is_red = false;
+				if left {
+					set_colour(&mut top.right, true);
+				} else {
+					set_colour(&mut top.left, true);
+				}
+				InsertionResult::Standard
+			}
+		}
+	}
+
+	///Takes a node with at most one child out, returns its entry and whether the black height dropped
+	fn unlink(link: &mut Ptr<Option<TreeNode<K,V,S>>>) -> (Ptr<(K,V)>, bool) {
+		let (child, entry, is_red) = {
+			let node = link.as_ref().as_ref().unwrap();
+			let child = if node.left.is_some() { node.left.clone() } else { node.right.clone() };
+			(child, node.entry.clone(), node.is_red)
+		};
+
+		*link = child;
+		if link.is_some() {
+			//only child must be a red leaf, take over our black
+			set_colour(link, false);
+			(entry, false)
+		} else {
+			(entry, !is_red)
+		}
+	}
+
+	fn remove_link<Q>(cmp: &C, link: &mut Ptr<Option<TreeNode<K,V,S>>>, key: &Q, nil: &Ptr<Option<TreeNode<K,V,S>>>) -> Option<(Ptr<(K,V)>, bool)> where K: Borrow<Q>, Q: ?Sized, C: Comparator<Q> {
+		let (ordering, two_children) = match **link {
+			None => return None,
+			Some(ref node) => (cmp.compare(key, node.key().borrow()), node.left.is_some() && node.right.is_some())
+		};
+
+		match ordering {
+			Ordering::Less => {
+				let node = node_mut(link);
+				let (entry, shortened) = Self::remove_link(cmp, &mut node.left, key, nil)?;
+				refresh(node);
+				Some((entry, shortened && Self::rebalance_left(link, nil)))
+			},
+			Ordering::Greater => {
+				let node = node_mut(link);
+				let (entry, shortened) = Self::remove_link(cmp, &mut node.right, key, nil)?;
+				refresh(node);
+				Some((entry, shortened && Self::rebalance_right(link, nil)))
+			},
+			Ordering::Equal if two_children => {
+				//swap in the in-order successor's entry
+				let node = node_mut(link);
+				let (successor, shortened) = Self::remove_min_link(&mut node.right, nil);
+				let entry = mem::replace(&mut node.entry, successor);
+				refresh(node);
+				Some((entry, shortened && Self::rebalance_right(link, nil)))
+			},
+			Ordering::Equal => Some(Self::unlink(link))
+		}
+	}
+
+	fn remove_min_link(link: &mut Ptr<Option<TreeNode<K,V,S>>>, nil: &Ptr<Option<TreeNode<K,V,S>>>) -> (Ptr<(K,V)>, bool) {
+		if link.as_ref().as_ref().unwrap().left.is_none() {
+			return Self::unlink(link);
+		}
+
+		let node = node_mut(link);
+		let (entry, shortened) = Self::remove_min_link(&mut node.left, nil);
+		refresh(node);
+		(entry, shortened && Self::rebalance_left(link, nil))
+	}
+
+	///Left subtree is one black node shorter than the right, returns whether the whole subtree still is
+	fn rebalance_left(link: &mut Ptr<Option<TreeNode<K,V,S>>>, nil: &Ptr<Option<TreeNode<K,V,S>>>) -> bool {
+		let node = node_mut(link);
+		if node.left.is_red() {
+			//blacken the short side
+			set_colour(&mut node.left, false);
+			return false;
+		}
+
+		let is_red = node.is_red;
+		let (sibling_red, far_red, near_red) = {
+			let sibling = node.right.as_ref().as_ref().unwrap();
+			(sibling.is_red, sibling.right.is_red(), sibling.left.is_red())
+		};
+
+		if sibling_red {
+			//rotate left, the red parent then absorbs the shortfall
+			rotate_left(link, nil);
+			let top = node_mut(link);
+			top.is_red = false;
+			set_colour(&mut top.left, true);
+			Self::rebalance_left(&mut top.left, nil);
+			false
+		} else if far_red || near_red {
+			if !far_red {
+				//rotate right then left
+				rotate_right(&mut node.right, nil);
+			}
+			rotate_left(link, nil);
+			let top = node_mut(link);
+			top.is_red = is_red;
+			set_colour(&mut top.left, false);
+			set_colour(&mut top.right, false);
+			false
+		} else {
+			//recolour sibling, pass the shortfall up if we were already black
+			set_colour(&mut node.right, true);
+			node.is_red = false;
+			!is_red
+		}
+	}
+
+	///Right subtree is one black node shorter than the left, returns whether the whole subtree still is
+	fn rebalance_right(link: &mut Ptr<Option<TreeNode<K,V,S>>>, nil: &Ptr<Option<TreeNode<K,V,S>>>) -> bool {
+		let node = node_mut(link);
+		if node.right.is_red() {
+			//blacken the short side
+			set_colour(&mut node.right, false);
+			return false;
+		}
+
+		let is_red = node.is_red;
+		let (sibling_red, far_red, near_red) = {
+			let sibling = node.left.as_ref().as_ref().unwrap();
+			(sibling.is_red, sibling.left.is_red(), sibling.right.is_red())
+		};
+
+		if sibling_red {
+			//rotate right, the red parent then absorbs the shortfall
+			rotate_right(link, nil);
+			let top = node_mut(link);
+			top.is_red = false;
+			set_colour(&mut top.right, true);
+			Self::rebalance_right(&mut top.right, nil);
+			false
+		} else if far_red || near_red {
+			if !far_red {
+				//rotate left then right
+				rotate_left(&mut node.left, nil);
+			}
+			rotate_right(link, nil);
+			let top = node_mut(link);
+			top.is_red = is_red;
+			set_colour(&mut top.left, false);
+			set_colour(&mut top.right, false);
+			false
+		} else {
+			//recolour sibling, pass the shortfall up if we were already black
+			set_colour(&mut node.left, true);
+			node.is_red = false;
+			!is_red
+		}
+	}
+}
+
+impl<K,V,S,C> Default for TreeTransient<K,V,S,C> where C: Comparator<K> + Default, S: Summary<K,V> {
+	fn default() -> TreeTransient<K,V,S,C> {
+		TreeTransient::new()
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Transient starting from this version, which itself stays untouched
+	pub fn transient(&self) -> TreeTransient<K,V,S,C> {
+		TreeTransient {root: self.root.clone(), nil: Ptr::new(None), cmp: self.cmp.clone()}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::mem;
+
+	use super::TreeTransient;
+	use super::super::{Ptr, Tree};
+	use super::super::tests::{build, check_tree, random_steps};
+
+	#[test]
+	fn test_transient_remove_missing() {
+		let tree = build(0..100);
+		let mut transient = tree.transient();
+		assert_eq!(transient.remove(&1000), None);
+		assert_eq!(transient.remove(&-5), None);
+		assert!(Ptr::ptr_eq(&transient.freeze().root, &tree.root));
+	}
+
+	#[test]
+	fn test_transient_build() {
+		let mut transient = TreeTransient::new();
+		for key in 0..500 {
+			assert_eq!(transient.insert((key * 37) % 500, key), None);
+		}
+		assert_eq!(transient.len(), 500);
+		assert_eq!(transient.insert(37, -1), Some(1));
+
+		let tree: Tree<i32,i32> = transient.freeze();
+		check_tree(&tree);
+		assert_eq!(tree.len(), 500);
+		assert_eq!(tree.find(&37), Some(&-1));
+		assert!(tree.keys().cloned().eq(0..500));
+	}
+
+	#[test]
+	fn test_transient_sequence() {
+		let mut transient = TreeTransient::new();
+		let mut present = [false; 200];
+
+		random_steps(0x6b43_a9b5, 200, 4000, |step, key, insert| {
+			if insert {
+				let old = transient.insert(key, key * 10);
+				assert_eq!(old.is_some(), present[key as usize]);
+				present[key as usize] = true;
+			} else {
+				let old = transient.remove(&key);
+				assert_eq!(old, if present[key as usize] { Some(key * 10) } else { None });
+				present[key as usize] = false;
+			}
+
+			//freezing a snapshot now and then forces later writes to copy
+			if step % 100 == 0 {
+				let tree = mem::take(&mut transient).freeze();
+				check_tree(&tree);
+				transient = tree.transient();
+			}
+		});
+
+		let tree = transient.freeze();
+		check_tree(&tree);
+		for (key, &is_present) in present.iter().enumerate() {
+			assert_eq!(tree.find(&(key as i32)).is_some(), is_present);
+		}
+	}
+
+	#[test]
+	fn test_transient_leaves_source() {
+		let base = build(0..100);
+
+		let mut transient = base.transient();
+		for key in 0..50 {
+			transient.remove(&(key * 2));
+		}
+		transient.insert(1000, 0);
+		let edited = transient.freeze();
+
+		check_tree(&base);
+		check_tree(&edited);
+		assert_eq!(base.len(), 100);
+		assert!(base.keys().cloned().eq(0..100));
+		assert_eq!(edited.len(), 51);
+		assert_eq!(edited.find(&2), None);
+		assert_eq!(edited.find(&3), Some(&30));
+	}
+
+	#[test]
+	fn test_transient_in_place() {
+		let mut transient = TreeTransient::new();
+		for key in 0..100 {
+			transient.insert(key, key);
+		}
+
+		//uniquely owned, so replacing a value keeps every node where it is
+		let root = Ptr::as_ptr(&transient.root);
+		transient.insert(50, -1);
+		assert_eq!(Ptr::as_ptr(&transient.root), root);
+
+		//a frozen copy still holds the root, so the next write copies the path
+		let frozen: Tree<i32,i32> = transient.freeze();
+		let mut transient = frozen.transient();
+		transient.insert(60, -1);
+		assert!(Ptr::as_ptr(&transient.root) != root);
+		assert_eq!(frozen.find(&60), Some(&60));
+	}
+}
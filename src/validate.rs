@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::{Comparator, HasColour, Ptr, Summary, Tree, TreeNode};
+
+///Broken invariant found by `Tree::check_invariants`, naming the keys involved
+#[derive(Debug, PartialEq, Eq)]
+pub enum Violation<'a,K> {
+	///Root is coloured red
+	RedRoot(&'a K),
+	///Red node has a red child
+	RedRed {parent: &'a K, child: &'a K},
+	///Paths through the two children pass different numbers of black nodes
+	BlackHeight {key: &'a K, left: usize, right: usize},
+	///Key is not above the key before it in order
+	OutOfOrder {prev: &'a K, key: &'a K},
+	///Stored subtree size does not match the entries below the node
+	Size {key: &'a K, stored: usize, actual: usize},
+}
+
+impl<'a,K> fmt::Display for Violation<'a,K> where K: fmt::Debug {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Violation::RedRoot(key) => write!(f, "root {:?} is red", key),
+			Violation::RedRed {parent, child} => write!(f, "red node {:?} has red child {:?}", parent, child),
+			Violation::BlackHeight {key, left, right} => write!(f, "black heights below {:?} differ, {} on the left and {} on the right", key, left, right),
+			Violation::OutOfOrder {prev, key} => write!(f, "key {:?} follows {:?}", key, prev),
+			Violation::Size {key, stored, actual} => write!(f, "node {:?} stores size {} but holds {} entries", key, stored, actual)
+		}
+	}
+}
+
+///Walks a subtree in order collecting violations, returns its black height and entry count
+fn check_link<'a,K,V,S,C>(cmp: &C, current: &'a Ptr<Option<TreeNode<K,V,S>>>, prev: &mut Option<&'a K>, violations: &mut Vec<Violation<'a,K>>) -> (usize, usize) where C: Comparator<K> {
+	match **current {
+		None => (0, 0),
+		Some(ref node) => {
+			let (left_height, left_size) = check_link(cmp, &node.left, prev, violations);
+
+			if let Some(prev) = *prev {
+				if cmp.compare(prev, node.key()) != Ordering::Less {
+					violations.push(Violation::OutOfOrder {prev, key: node.key()});
+				}
+			}
+			*prev = Some(node.key());
+
+			let (right_height, right_size) = check_link(cmp, &node.right, prev, violations);
+
+			if node.is_red() {
+				for child in [&node.left, &node.right].iter() {
+					if let Some(ref child) = ***child {
+						if child.is_red() {
+							violations.push(Violation::RedRed {parent: node.key(), child: child.key()});
+						}
+					}
+				}
+			}
+			if left_height != right_height {
+				violations.push(Violation::BlackHeight {key: node.key(), left: left_height, right: right_height});
+			}
+			let actual = left_size + right_size + 1;
+			if node.size != actual {
+				violations.push(Violation::Size {key: node.key(), stored: node.size, actual});
+			}
+
+			//carry on with the taller side so a single fault is only reported once
+			let height = left_height.max(right_height);
+			(if node.is_red() { height } else { height + 1 }, actual)
+		}
+	}
+}
+
+impl<K,V,S,C> Tree<K,V,S,C> where C: Comparator<K> + Clone, S: Summary<K,V> {
+	///Checks colouring, black heights, key order and sizes, returning every violation found.
+	///Trees built through the public API always come back with none.
+	pub fn check_invariants(&self) -> Vec<Violation<'_,K>> {
+		let mut violations = Vec::new();
+
+		if self.root.is_red() {
+			let root = self.root.as_ref().as_ref().unwrap();
+			violations.push(Violation::RedRoot(root.key()));
+		}
+		check_link(&self.cmp, &self.root, &mut None, &mut violations);
+
+		violations
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Violation;
+	use super::super::{Natural, Ptr, Tree, TreeNode};
+	use super::super::tests::{nil, node};
+
+	#[test]
+	fn test_valid_trees() {
+		let mut tree = Tree::new();
+		assert_eq!(tree.check_invariants(), vec![]);
+
+		for key in 0..200 {
+			tree = tree.insert((key * 37) % 200, key).unwrap();
+			assert_eq!(tree.check_invariants(), vec![]);
+		}
+		for key in 0..100 {
+			tree = tree.remove(&(key * 2)).unwrap().0;
+			assert_eq!(tree.check_invariants(), vec![]);
+		}
+	}
+
+	#[test]
+	fn test_red_violations() {
+		//    R5
+		//   /
+		//  R3
+		let root = node(true, 5, node(true, 3, nil(), nil()), nil());
+		let tree = Tree {root, cmp: Natural};
+
+		let violations = tree.check_invariants();
+		assert!(violations.contains(&Violation::RedRoot(&5)));
+		assert!(violations.contains(&Violation::RedRed {parent: &5, child: &3}));
+		assert_eq!(violations.len(), 2);
+		assert_eq!(violations[1].to_string(), "red node 5 has red child 3");
+	}
+
+	#[test]
+	fn test_black_height_violation() {
+		//    B5
+		//   /  \
+		//  B3   -
+		let root = node(false, 5, node(false, 3, nil(), nil()), nil());
+		let tree = Tree {root, cmp: Natural};
+
+		assert_eq!(tree.check_invariants(), vec![Violation::BlackHeight {key: &5, left: 1, right: 0}]);
+	}
+
+	#[test]
+	fn test_order_violation() {
+		//    B5
+		//   /  \
+		//  R7   R6
+		let root = node(false, 5, node(true, 7, nil(), nil()), node(true, 6, nil(), nil()));
+		let tree = Tree {root, cmp: Natural};
+
+		//only the step down from 7 to 5 is out of order
+		assert_eq!(tree.check_invariants(), vec![Violation::OutOfOrder {prev: &7, key: &5}]);
+	}
+
+	#[test]
+	fn test_size_violation() {
+		let tree = Tree {cmp: Natural, root: Ptr::new(Some(TreeNode {
+			is_red: false,
+			size: 3,
+			summary: (),
+			entry: Ptr::new((5, 5)),
+			left: node(true, 4, nil(), nil()),
+			right: nil()
+		}))};
+
+		assert_eq!(tree.check_invariants(), vec![Violation::Size {key: &5, stored: 3, actual: 2}]);
+	}
+}